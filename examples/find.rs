@@ -1,4 +1,4 @@
-use displayz::query_displays;
+use displayz::{query_displays, DisplayQuery};
 
 /// Finds displays by name and index
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -6,10 +6,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Discovered displays:\n{}", display_set);
 
     // find a display by filtering
-    match display_set
-        .displays()
-        .find(|display| display.name() == "\\\\.\\DISPLAY3")
-    {
+    match display_set.find(&DisplayQuery::new().name("\\\\.\\DISPLAY3")) {
         Some(display) => println!("Display3 found. Is primary? {}", display.is_primary()),
         None => println!("Display 3 not found"),
     }
@@ -21,12 +18,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     // getting the displays properties by index
-    if let Some(new_primary) = display_set.get(0) {
-        if let Some(settings) = new_primary.settings() {
-            println!(
-                "Position of display with index 0: {}",
-                (*settings.borrow()).position
-            );
+    if let Some(display) = display_set.get(0) {
+        if let Ok(position) = display.with_settings(|settings| settings.position) {
+            println!("Position of display with index 0: {}", position);
         }
     }
 
@@ -1,4 +1,4 @@
-use displayz::{query_displays, refresh};
+use displayz::query_displays;
 
 /// Sets a display to be the new primary display
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -10,15 +10,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .displays()
         .find(|display| display.name() == "\\\\.\\DISPLAY2");
 
-    // set display primary
+    // set display primary and apply the changed settings
     if let Some(display) = display {
-        display.set_primary()?;
-        // display_set.set_primary(&display)?; // this works, too
+        display.make_primary()?;
+        // or display.set_primary()? and display_set.apply_and_refresh()? to batch it with other changes
     }
 
-    // apply the changed settings
-    display_set.apply()?;
-    refresh()?;
-
     Ok(())
 }
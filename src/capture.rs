@@ -0,0 +1,105 @@
+use crate::{Display, DisplayError};
+
+type Result<T = ()> = std::result::Result<T, DisplayError>;
+
+/// The captured content of a display as raw RGBA pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageBuffer {
+    pub width: u32,
+    pub height: u32,
+    /// The pixels row by row, top to bottom, with four bytes (red, green, blue, alpha) per pixel
+    pub data: Vec<u8>,
+}
+
+impl Display<'_> {
+    /// Captures the current content of this display with `BitBlt`
+    /// Fails for inactive displays. The alpha channel is always opaque. Mock displays capture a black image.
+    pub fn capture(&self) -> Result<ImageBuffer> {
+        let bounds = self
+            .bounds()?
+            .ok_or_else(|| DisplayError::NoSettings(self.name().to_string()))?;
+        let (width, height) = (bounds.size.width, bounds.size.height);
+
+        if self.is_mock() {
+            return Ok(ImageBuffer {
+                width,
+                height,
+                data: [0, 0, 0, u8::MAX].repeat(width as usize * height as usize),
+            });
+        }
+
+        let mut data = capture_screen(bounds.left(), bounds.top(), width, height)
+            .ok_or_else(|| DisplayError::Capture(self.name().to_string()))?;
+        // GDI returns BGRA with an undefined alpha channel
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+            pixel[3] = u8::MAX;
+        }
+
+        Ok(ImageBuffer {
+            width,
+            height,
+            data,
+        })
+    }
+}
+
+/// Copies the given area of the virtual screen into a top-down 32 bit BGRA buffer
+fn capture_screen(left: i32, top: i32, width: u32, height: u32) -> Option<Vec<u8>> {
+    use std::mem;
+
+    use windows::Win32::{
+        Foundation::HWND,
+        Graphics::Gdi::{
+            BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC,
+            GetDIBits, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+            DIB_RGB_COLORS, SRCCOPY,
+        },
+    };
+
+    let (cx, cy) = (i32::try_from(width).ok()?, i32::try_from(height).ok()?);
+    let mut data = vec![0u8; width as usize * height as usize * 4];
+
+    let mut info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: cx,
+            // a negative height requests a top-down bitmap
+            biHeight: -cy,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB as u32,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    unsafe {
+        let screen = GetDC(HWND::default());
+        let memory = CreateCompatibleDC(screen);
+        let bitmap = CreateCompatibleBitmap(screen, cx, cy);
+        let previous = SelectObject(memory, bitmap);
+
+        let copied = BitBlt(memory, 0, 0, cx, cy, screen, left, top, SRCCOPY).as_bool();
+        SelectObject(memory, previous);
+        let lines = if copied {
+            GetDIBits(
+                memory,
+                bitmap,
+                0,
+                height,
+                data.as_mut_ptr().cast(),
+                &mut info,
+                DIB_RGB_COLORS,
+            )
+        } else {
+            0
+        };
+
+        DeleteObject(bitmap);
+        DeleteDC(memory);
+        ReleaseDC(HWND::default(), screen);
+
+        (lines == cy).then_some(data)
+    }
+}
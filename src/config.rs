@@ -0,0 +1,358 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::display::{Display, DisplayError, DisplaySet};
+use crate::properties::PartialDisplaySettings;
+
+type Result<T = (), E = DisplayError> = std::result::Result<T, E>;
+
+/// Errors that can occur while reading or writing a display config
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Could not read the config file")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid JSON config")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "toml")]
+    #[error("Invalid TOML config")]
+    TomlDe(#[from] toml::de::Error),
+    #[cfg(feature = "toml")]
+    #[error("Could not write the TOML config")]
+    TomlSer(#[from] toml::ser::Error),
+    #[error("Unsupported config format: {0}")]
+    UnsupportedFormat(String),
+}
+
+/// The desired state of a set of displays, e.g. read from a config file
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    /// The desired state of each display
+    pub displays: Vec<DisplayConfigEntry>,
+}
+
+/// The desired state of a single display
+/// The display is matched by its `key` first and by its `name` if no key is given.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayConfigEntry {
+    /// The device key of the display
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// The name of the display, e.g. `\\.\DISPLAY1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Whether the display should become the primary display
+    #[serde(default)]
+    pub primary: bool,
+    /// Whether applying the config fails if the display is missing
+    #[serde(default)]
+    pub required: bool,
+    /// The settings to apply to the display
+    pub settings: PartialDisplaySettings,
+}
+
+impl DisplayConfigEntry {
+    /// Whether this entry describes the given display
+    pub fn matches(&self, display: &Display) -> bool {
+        match (&self.key, &self.name) {
+            (Some(key), _) => display.key() == key,
+            (None, Some(name)) => display.name() == name,
+            (None, None) => false,
+        }
+    }
+
+    /// Returns the key or name that identifies this entry
+    pub fn describe(&self) -> &str {
+        self.key
+            .as_deref()
+            .or(self.name.as_deref())
+            .unwrap_or("<unnamed>")
+    }
+}
+
+/// The result of matching a config against a display set
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ConfigMatches {
+    /// Pairs of the entry index and the index of the matched display
+    pub matched: Vec<(usize, usize)>,
+    /// Indices of the entries without an active display
+    pub unmatched: Vec<usize>,
+}
+
+impl DisplayConfig {
+    /// Parses a config from a JSON string
+    pub fn from_json_str(json: &str) -> Result<Self, ConfigError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Serializes the config to a pretty-printed JSON string
+    pub fn to_json_string(&self) -> Result<String, ConfigError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses a config from a TOML string
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(toml: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Serializes the config to a pretty-printed TOML string
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        // TOML requires plain values before tables; going through a `Value` sorts them accordingly
+        let value = toml::Value::try_from(self)?;
+        Ok(toml::to_string_pretty(&value)?)
+    }
+
+    /// Reads a config from a file, picking the format by its extension
+    /// Files ending in `.toml` are parsed as TOML (requires the `toml` feature), all others as JSON.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            #[cfg(feature = "toml")]
+            Some(extension) if extension.eq_ignore_ascii_case("toml") => {
+                Self::from_toml_str(&content)
+            }
+            #[cfg(not(feature = "toml"))]
+            Some(extension) if extension.eq_ignore_ascii_case("toml") => {
+                Err(ConfigError::UnsupportedFormat(extension.to_string()))
+            }
+            _ => Self::from_json_str(&content),
+        }
+    }
+
+    /// Creates a config that describes the current state of all active displays
+    pub fn from_display_set(display_set: &DisplaySet) -> Result<Self> {
+        let displays = display_set
+            .iter_active()
+            .map(|display| {
+                Ok(DisplayConfigEntry {
+                    key: Some(display.key().to_string()),
+                    name: Some(display.name().to_string()),
+                    primary: display.is_primary(),
+                    required: false,
+                    settings: display.with_settings(|settings| (*settings).into())?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { displays })
+    }
+
+    /// Matches the entries of this config against the active displays of `display_set`
+    /// Each display is matched by at most one entry.
+    pub fn match_displays(&self, display_set: &DisplaySet) -> ConfigMatches {
+        let mut matches = ConfigMatches::default();
+        for (entry_index, entry) in self.displays.iter().enumerate() {
+            let target = display_set.iter_active().find(|display| {
+                entry.matches(display)
+                    && !matches
+                        .matched
+                        .iter()
+                        .any(|(_, index)| *index == display.index())
+            });
+            match target {
+                Some(display) => matches.matched.push((entry_index, display.index())),
+                None => matches.unmatched.push(entry_index),
+            }
+        }
+        matches
+    }
+
+    /// Writes the settings of this config onto the matching displays of `display_set`
+    /// Only the fields present in an entry are overwritten, all others keep their current value.
+    /// Fails without changing `display_set` if a required entry (or any entry, if `strict`) has no match.
+    /// Requires a call to `apply` and `refresh` afterwards
+    pub fn apply_to(&self, display_set: &DisplaySet, strict: bool) -> Result<ConfigMatches> {
+        let matches = self.match_displays(display_set);
+        if let Some(entry) = matches
+            .unmatched
+            .iter()
+            .map(|index| &self.displays[*index])
+            .find(|entry| strict || entry.required)
+        {
+            return Err(DisplayError::NoMatch(entry.describe().to_string()));
+        }
+
+        let mut primary = None;
+        for (entry_index, display_index) in &matches.matched {
+            let entry = &self.displays[*entry_index];
+            let display = display_set
+                .get(*display_index)
+                .ok_or(DisplayError::NotFound(*display_index))?;
+            display.with_settings_mut(|current| entry.settings.apply_to(current))?;
+            if entry.primary {
+                primary = Some(display);
+            }
+        }
+        if let Some(display) = primary.filter(|display| !display.is_primary()) {
+            display.set_primary()?;
+        }
+        Ok(matches)
+    }
+}
+
+/// Saved configs per combination of connected displays, e.g. to arrange the displays automatically when docking
+/// The configs are keyed by `DisplaySet::topology_fingerprint`, written as hex string so the store can be saved as TOML, too.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ProfileStore {
+    /// The saved configs by the hex-encoded fingerprint of their topology
+    pub profiles: BTreeMap<String, DisplayConfig>,
+}
+
+impl ProfileStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a store from a JSON string
+    pub fn from_json_str(json: &str) -> Result<Self, ConfigError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Serializes the store to a pretty-printed JSON string
+    pub fn to_json_string(&self) -> Result<String, ConfigError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses a store from a TOML string
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(toml: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Serializes the store to a pretty-printed TOML string
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        // see `DisplayConfig::to_toml_string`
+        let value = toml::Value::try_from(self)?;
+        Ok(toml::to_string_pretty(&value)?)
+    }
+
+    /// Returns the config saved for the given topology `fingerprint`
+    pub fn get(&self, fingerprint: u64) -> Option<&DisplayConfig> {
+        self.profiles.get(&fingerprint_key(fingerprint))
+    }
+
+    /// Saves the `config` for the given topology `fingerprint`, returning the config it replaces
+    pub fn insert(&mut self, fingerprint: u64, config: DisplayConfig) -> Option<DisplayConfig> {
+        self.profiles.insert(fingerprint_key(fingerprint), config)
+    }
+
+    /// Saves the current state of the displays of `display_set` for its topology
+    pub fn save_current(&mut self, display_set: &DisplaySet) -> Result {
+        let config = DisplayConfig::from_display_set(display_set)?;
+        self.insert(display_set.topology_fingerprint(), config);
+
+        Ok(())
+    }
+
+    /// Applies the config saved for the current topology of `live`, including `apply` and `refresh`
+    /// Returns `false` without changing anything if no config is saved for the topology.
+    pub fn apply_matching(&self, live: &DisplaySet) -> Result<bool> {
+        let config = match self.get(live.topology_fingerprint()) {
+            Some(config) => config,
+            None => return Ok(false),
+        };
+
+        config.apply_to(live, false)?;
+        live.apply_and_refresh()?;
+
+        Ok(true)
+    }
+}
+
+/// Formats a topology fingerprint as key of `ProfileStore::profiles`
+fn fingerprint_key(fingerprint: u64) -> String {
+    format!("{:016x}", fingerprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockDisplay;
+    use crate::{DisplaySettings, FixedOutput, Orientation, Position, Resolution};
+
+    fn current() -> DisplaySettings {
+        DisplaySettings {
+            position: Position::new(1920, 0),
+            resolution: Resolution::new(1920, 1080),
+            orientation: Orientation::Portrait,
+            fixed_output: FixedOutput::Center,
+        }
+    }
+
+    #[test]
+    fn omitted_fields_keep_their_current_value() {
+        let partial = PartialDisplaySettings {
+            resolution: Some(Resolution::new(2560, 1440)),
+            ..Default::default()
+        };
+        let mut settings = current();
+        partial.apply_to(&mut settings);
+        assert_eq!(
+            settings,
+            DisplaySettings {
+                resolution: Resolution::new(2560, 1440),
+                ..current()
+            }
+        );
+
+        let mut settings = current();
+        PartialDisplaySettings::default().apply_to(&mut settings);
+        assert_eq!(settings, current());
+    }
+
+    #[test]
+    fn json_config_parses_sparse_settings() {
+        let config = DisplayConfig::from_json_str(
+            r#"{ "displays": [{ "name": "a", "settings": { "orientation": "landscapeflipped" } }] }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.displays[0].settings,
+            PartialDisplaySettings {
+                orientation: Some(Orientation::LandscapeFlipped),
+                ..Default::default()
+            }
+        );
+
+        let display_set = DisplaySet::from_mock(vec![MockDisplay::new("a", current()).primary()]);
+        config.apply_to(&display_set, true).unwrap();
+        assert_eq!(
+            display_set
+                .primary()
+                .with_settings(|settings| *settings)
+                .unwrap(),
+            DisplaySettings {
+                orientation: Orientation::LandscapeFlipped,
+                ..current()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn toml_config_parses_sparse_settings() {
+        let config = DisplayConfig::from_toml_str(
+            r#"
+            [[displays]]
+            name = "a"
+
+            [displays.settings.position]
+            x = -1920
+            y = 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.displays[0].settings,
+            PartialDisplaySettings {
+                position: Some(Position::new(-1920, 0)),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(windows)]
+use winsafe::{co, GmidxEnum, DEVMODE, DISPLAY_DEVICE};
+
+use crate::display::DisplayError;
+
+type Result<T = ()> = std::result::Result<T, DisplayError>;
+
+/// Returns a readable dump of the raw `DISPLAY_DEVICE` and `DEVMODE` of every display device, e.g. for bug reports
+/// Does not convert the values, so it works for displays `query_displays` fails on.
+/// Errors when reading the settings of a device are written into the dump instead of aborting it.
+/// With `verbose`, the settings saved in the registry are dumped as well.
+#[cfg(windows)]
+pub fn raw_display_info(verbose: bool) -> Result<String> {
+    let mut dump = String::new();
+
+    let mut display_device = DISPLAY_DEVICE::default();
+    for dev_num in 0.. {
+        if !winsafe::EnumDisplayDevices(None, dev_num, &mut display_device, co::EDD::NoValue)? {
+            break;
+        }
+
+        let name = display_device.DeviceName();
+        dump += &format!(
+            "Device {}: {}\n  string: {}\n  id: {}\n  key: {}\n  state flags: {:?}\n",
+            dev_num,
+            name,
+            display_device.DeviceString(),
+            display_device.DeviceID(),
+            display_device.DeviceKey(),
+            display_device.StateFlags
+        );
+
+        dump += &dump_devmode(&name, "current settings", co::ENUM_SETTINGS::CURRENT);
+        if verbose {
+            dump += &dump_devmode(&name, "registry settings", co::ENUM_SETTINGS::REGISTRY);
+        }
+    }
+
+    Ok(dump)
+}
+
+/// Always fails, as display devices can only be enumerated on Windows
+#[cfg(not(windows))]
+pub fn raw_display_info(_verbose: bool) -> Result<String> {
+    Err(DisplayError::Unsupported(
+        "display devices can only be enumerated on Windows",
+    ))
+}
+
+/// Formats the raw `DEVMODE` of the display with the given `name`, or the error reading it
+#[cfg(windows)]
+fn dump_devmode(name: &str, label: &str, settings: co::ENUM_SETTINGS) -> String {
+    let mut devmode = DEVMODE::default();
+    if let Err(err) =
+        winsafe::EnumDisplaySettings(Some(name), GmidxEnum::Enum(settings), &mut devmode)
+    {
+        return format!("  {}: failed with {}\n", label, err);
+    }
+
+    let position = devmode.dmPosition();
+    format!(
+        "  {}:\n    fields: {:?}\n    mode: {}x{} @ {} Hz, {} bits per pixel\n    position: {},{}\n    orientation: {:?}\n    fixed output: {:?}\n",
+        label,
+        devmode.dmFields,
+        devmode.dmPelsWidth,
+        devmode.dmPelsHeight,
+        devmode.dmDisplayFrequency,
+        devmode.dmBitsPerPel,
+        position.x,
+        position.y,
+        devmode.dmDisplayOrientation(),
+        devmode.dmDisplayFixedOutput()
+    )
+}
@@ -1,27 +1,71 @@
 use core::fmt;
-use std::cell::{Cell, RefCell};
+use std::cell::{Cell, OnceCell, RefCell, RefMut};
+use std::thread;
+use std::time::Duration;
 
 use thiserror::Error;
-use winsafe::{co, EnumDisplayDevices, DISPLAY_DEVICE};
+#[cfg(windows)]
+use winsafe::co;
 
+#[cfg(windows)]
+use crate::properties::change_display_settings;
 use crate::{
-    properties::{DisplayProperties, DisplaySettings, Position},
+    layout::{arrange, recompute_origin, Arrangement},
+    properties::{
+        ApplyOptions, DirtyFields, DisplayProperties, DisplaySettings, Orientation, Position,
+        Rectangle, Resolution,
+    },
+    snapshot::{DisplaySnapshot, DisplaySnapshotEntry},
     DisplayPropertiesError,
 };
 
+#[cfg(any(test, feature = "mock"))]
+use crate::mock::MockDisplay;
+
 /// Error type for the display module
 #[derive(Error, Debug)]
 pub enum DisplayError {
     #[error("Error in DisplayProperties")]
     Properties(#[from] DisplayPropertiesError),
+    #[cfg(windows)]
     #[error("Error when calling the Windows API")]
     WinAPI(#[from] co::ERROR),
     #[error("Only active displays can used as a primary display")]
     PrimaryDisplay,
     #[error("Display {0} has no settings")]
     NoSettings(String),
+    #[error("Display with index {0} not found")]
+    NotFound(usize),
+    #[cfg(windows)]
     #[error("Failed to commit the changes; Returned flags: {0}")]
     FailedToCommit(co::DISP_CHANGE),
+    #[error("Settings of display {0} are already borrowed")]
+    Busy(String),
+    #[error(
+        "The display set was created from a snapshot; use `apply_to` to apply it to live displays"
+    )]
+    Detached,
+    #[error("No active live display matches the display with key {0}")]
+    NoMatch(String),
+    #[error("The computer must be restarted for the changes to take effect")]
+    RebootRequired,
+    #[error("Not supported on this platform: {0}")]
+    Unsupported(&'static str),
+    #[cfg(feature = "async")]
+    #[error("The blocking task applying the changes failed")]
+    Join(#[source] tokio::task::JoinError),
+    #[cfg(feature = "capture")]
+    #[error("Failed to capture display {0}")]
+    Capture(String),
+    #[cfg(feature = "topology")]
+    #[error("Failed to change the display topology; Returned error code: {0}")]
+    Topology(i32),
+    #[cfg(feature = "dxgi")]
+    #[error("Error when calling DXGI")]
+    Dxgi(#[from] windows::core::Error),
+    #[cfg(feature = "dxgi")]
+    #[error("No DXGI output found for display {0}")]
+    NoDxgiOutput(String),
 }
 
 type Result<T = ()> = std::result::Result<T, DisplayError>;
@@ -35,6 +79,30 @@ pub struct Display<'a> {
     display_set: &'a DisplaySet,
 }
 
+/// A stable identifier of a display that, unlike its index, survives re-querying
+/// It is based on the device key of the display, see `DisplaySet::resolve`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisplayId(String);
+
+impl DisplayId {
+    /// Creates an identifier from the device key of a display
+    pub fn new(key: &str) -> Self {
+        Self(key.to_string())
+    }
+
+    /// Returns the device key of the display
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for DisplayId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Generates getter for properties of a display
 macro_rules! get_properties_str {
     ($field:ident) => {
@@ -49,7 +117,7 @@ impl Display<'_> {
         self.index
     }
 
-    fn properties(&self) -> &DisplayProperties {
+    pub(crate) fn properties(&self) -> &DisplayProperties {
         &self.display_set.displays[self.index]
     }
 
@@ -57,10 +125,95 @@ impl Display<'_> {
     get_properties_str!(string);
     get_properties_str!(key);
 
+    /// Returns the PnP device instance path of this display, see `DisplayProperties::device_instance_path`
+    pub fn device_instance_path(&self) -> Option<String> {
+        self.properties().device_instance_path()
+    }
+
+    /// Returns a stable identifier of this display, see `DisplaySet::resolve`
+    pub fn id(&self) -> DisplayId {
+        DisplayId::new(self.key())
+    }
+
+    /// Whether this display belongs to a set of fake displays, see `DisplaySet::from_mock`
+    #[cfg(feature = "capture")]
+    pub(crate) fn is_mock(&self) -> bool {
+        self.display_set.mock
+    }
+
     pub fn settings(&self) -> &Option<RefCell<DisplaySettings>> {
         &self.properties().settings
     }
 
+    /// Reverts pending changes to the settings of this display that were not applied yet
+    pub fn discard_changes(&self) -> Result {
+        self.refresh_settings()
+    }
+
+    /// Calls `f` with the settings of this display
+    pub fn with_settings<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&DisplaySettings) -> R,
+    {
+        let settings = self
+            .settings()
+            .as_ref()
+            .ok_or_else(|| DisplayError::NoSettings(self.name().to_string()))?;
+
+        let settings = settings
+            .try_borrow()
+            .map_err(|_| DisplayError::Busy(self.name().to_string()))?;
+
+        Ok(f(&settings))
+    }
+
+    /// Calls `f` with the settings of this display, allowing to change them
+    /// Requires a call to `apply` and `refresh` afterwards
+    pub fn with_settings_mut<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut DisplaySettings) -> R,
+    {
+        let settings = self
+            .settings()
+            .as_ref()
+            .ok_or_else(|| DisplayError::NoSettings(self.name().to_string()))?;
+
+        let mut settings = settings
+            .try_borrow_mut()
+            .map_err(|_| DisplayError::Busy(self.name().to_string()))?;
+
+        Ok(f(&mut settings))
+    }
+
+    /// Sets the orientation of this display, swapping width and height of the resolution when rotating between landscape and portrait
+    /// Requires a call to `apply` and `refresh` afterwards
+    pub fn set_orientation(&self, orientation: Orientation) -> Result {
+        self.with_settings_mut(|settings| {
+            if settings.orientation.is_portrait() != orientation.is_portrait() {
+                settings.resolution =
+                    Resolution::new(settings.resolution.height, settings.resolution.width);
+            }
+            settings.orientation = orientation;
+        })
+    }
+
+    /// Returns the area the display covers on the desktop, or `None` if it has no settings
+    /// Fails if the settings are currently borrowed mutably, e.g. inside `with_settings_mut`
+    pub fn bounds(&self) -> Result<Option<Rectangle>> {
+        match self.settings() {
+            Some(_) => self.with_settings(|settings| Some(settings.bounds())),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the number of pixels of the current resolution, or `0` if the display has no settings
+    pub fn pixel_count(&self) -> Result<u64> {
+        Ok(self
+            .bounds()?
+            .map(|bounds| bounds.size.pixel_count())
+            .unwrap_or_default())
+    }
+
     pub fn is_primary(&self) -> bool {
         self.display_set.primary_display.get() == self.index
     }
@@ -69,31 +222,280 @@ impl Display<'_> {
         self.display_set.set_primary(self)
     }
 
+    /// Makes this display the primary display and commits the change right away
+    /// Also commits all other pending changes of the display set, see `DisplaySet::apply_and_refresh`
+    pub fn make_primary(&self) -> Result {
+        self.set_primary()?;
+        self.display_set.apply_and_refresh()
+    }
+
+    /// Sets the changes of this display, see `DisplaySet::apply_display`
     pub fn apply(&self) -> Result {
-        self.properties().apply().map_err(DisplayError::Properties)
+        self.display_set.apply_display(self.index)
+    }
+
+    /// Checks whether Windows would accept the given `settings` for this display, without changing anything
+    /// See `DisplayProperties::test_settings`
+    pub fn test_settings(&self, settings: &DisplaySettings) -> Result<bool> {
+        if self.display_set.mock {
+            return Ok(true);
+        }
+
+        self.properties()
+            .test_settings(settings)
+            .map_err(DisplayError::Properties)
+    }
+
+    /// Applies a resolution and refresh rate (in Hz) that the driver accepts but does not necessarily enumerate
+    /// Returns the result of the `CDS_TEST` check that precedes writing the mode, see `DisplayProperties::apply_custom_resolution`
+    /// Requires a call to `refresh` afterwards
+    #[cfg(windows)]
+    pub fn set_custom_resolution(
+        &self,
+        resolution: Resolution,
+        frequency: u32,
+    ) -> Result<co::DISP_CHANGE> {
+        self.display_set.ensure_live()?;
+        let tested = if self.display_set.mock {
+            self.with_settings_mut(|settings| settings.resolution = resolution)?;
+            co::DISP_CHANGE::SUCCESSFUL
+        } else {
+            self.properties()
+                .apply_custom_resolution(resolution, frequency)
+                .map_err(DisplayError::Properties)?
+        };
+        self.display_set.mark_applied(self.index);
+
+        Ok(tested)
+    }
+
+    /// Refreshes only this display to commit its changes, see `refresh`
+    /// Falls back to refreshing all displays if Windows rejects the call for a single display
+    pub fn refresh(&self) -> Result {
+        self.display_set.ensure_live()?;
+        if self.display_set.mock {
+            return Ok(());
+        }
+
+        refresh_display(self.name())
+    }
+
+    /// Re-fetches the settings of this display, e.g. after another process changed them
+    /// Mock displays revert to the settings they were created with or last applied, as there is no device to fetch them from.
+    pub fn refresh_settings(&self) -> Result {
+        if self.display_set.mock {
+            if let Some(applied) = self.display_set.applied[self.index].get() {
+                self.with_settings_mut(|settings| *settings = applied)?;
+            }
+            return Ok(());
+        }
+
+        self.properties()
+            .refresh_settings()
+            .map_err(DisplayError::Properties)?;
+        self.display_set.mark_applied(self.index);
+
+        Ok(())
+    }
+
+    /// Returns whether the settings of this display differ from the ones queried or last applied
+    /// Settings that are currently borrowed mutably count as changed
+    pub fn has_changes(&self) -> bool {
+        !self.dirty_fields().is_empty()
+    }
+
+    /// Returns the fields of the settings that differ from the ones queried or last applied
+    /// All fields count as changed if the settings are currently borrowed mutably
+    pub fn dirty_fields(&self) -> DirtyFields {
+        let settings = match self.settings() {
+            Some(settings) => settings,
+            None => return DirtyFields::default(),
+        };
+
+        match (
+            settings.try_borrow(),
+            self.display_set.applied[self.index].get(),
+        ) {
+            (Ok(settings), Some(applied)) => settings.changed_fields(&applied),
+            _ => DirtyFields::all(),
+        }
     }
 }
 
 /// A struct that represents a set of displays
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DisplaySet {
     /// The displays in this set
     displays: Vec<DisplayProperties>,
     /// The primary display
     primary_display: Cell<usize>,
+    /// The settings of each display as queried or last applied, to skip applying unchanged settings
+    #[cfg_attr(feature = "serde", serde(skip))]
+    applied: Vec<Cell<Option<DisplaySettings>>>,
+    /// Whether the set was created from a snapshot and is not backed by live displays
+    #[cfg_attr(feature = "serde", serde(skip))]
+    detached: bool,
+    /// Whether the set was created from fake displays, so writing its settings never reaches Windows
+    #[cfg_attr(feature = "serde", serde(skip))]
+    mock: bool,
+    /// The index and primary flag of each display written by a mock set, in order, to observe which writes reach the backend
+    #[cfg_attr(feature = "serde", serde(skip))]
+    mock_writes: RefCell<Vec<(usize, bool)>>,
+}
+
+impl PartialEq for DisplaySet {
+    /// Compares the displays and the primary display, ignoring which settings were applied already
+    fn eq(&self, other: &Self) -> bool {
+        self.displays == other.displays && self.primary_display == other.primary_display
+    }
 }
 
+impl Eq for DisplaySet {}
+
 impl DisplaySet {
+    /// Creates a display set, using the display flagged as primary as primary display
+    /// If no display is flagged, e.g. while Windows is reconfiguring the displays, the first active display at the origin is used, otherwise the first active display
+    fn new(displays: Vec<DisplayProperties>) -> Self {
+        let primary_display = displays
+            .iter()
+            .position(|display| display.primary.get())
+            .unwrap_or_else(|| {
+                let fallback = displays
+                    .iter()
+                    .position(|display| {
+                        display.settings.as_ref().is_some_and(|settings| {
+                            settings.borrow().position == Position::default()
+                        })
+                    })
+                    .or_else(|| displays.iter().position(|display| display.active))
+                    .unwrap_or(0);
+                if !displays.is_empty() {
+                    log::warn!(
+                        "No display is flagged as primary, using display {} instead",
+                        fallback
+                    );
+                }
+                fallback
+            });
+
+        let applied = displays
+            .iter()
+            .map(|display| Cell::new(display.settings.as_ref().map(|settings| *settings.borrow())))
+            .collect();
+
+        DisplaySet {
+            displays,
+            primary_display: Cell::new(primary_display),
+            applied,
+            detached: false,
+            mock: false,
+            mock_writes: RefCell::default(),
+        }
+    }
+
+    /// Creates a display set from a snapshot, e.g. one that was saved on another machine, to edit a layout offline
+    /// The set is not backed by live displays, so it can only be applied with `apply_to`
+    pub fn from_snapshot(snapshot: DisplaySnapshot) -> Self {
+        let primary_index = snapshot.primary_index;
+        let displays = snapshot
+            .displays
+            .into_iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let properties = DisplayProperties::from(entry);
+                properties.primary.set(index == primary_index);
+                properties
+            })
+            .collect();
+
+        DisplaySet {
+            detached: true,
+            ..Self::new(displays)
+        }
+    }
+
+    /// Creates a display set from fake displays, e.g. to test layout logic without hardware
+    /// Applying and refreshing the set never changes a real display; the settings count as applied right away.
+    #[cfg(any(test, feature = "mock"))]
+    pub fn from_mock(displays: Vec<MockDisplay>) -> Self {
+        DisplaySet {
+            mock: true,
+            ..Self::new(displays.into_iter().map(DisplayProperties::from).collect())
+        }
+    }
+
     /// Iterates over the displays in this set
-    pub fn displays(&self) -> impl ExactSizeIterator<Item = Display> {
+    pub fn displays(&self) -> impl ExactSizeIterator<Item = Display<'_>> {
         self.displays.iter().enumerate().map(|(index, _)| Display {
             index,
             display_set: self,
         })
     }
 
+    /// Returns the number of displays in this set
+    pub fn len(&self) -> usize {
+        self.displays.len()
+    }
+
+    /// Whether this set contains no displays
+    pub fn is_empty(&self) -> bool {
+        self.displays.is_empty()
+    }
+
+    /// Iterates over the active displays in this set
+    pub fn iter_active(&self) -> impl Iterator<Item = Display<'_>> {
+        self.displays()
+            .filter(|display| display.properties().active)
+    }
+
+    /// Iterates over the inactive displays in this set
+    pub fn iter_inactive(&self) -> impl Iterator<Item = Display<'_>> {
+        self.displays()
+            .filter(|display| !display.properties().active)
+    }
+
+    /// Returns the displays ordered top-to-bottom, then left-to-right
+    /// Inactive displays (without settings) come last, in enumeration order
+    pub fn displays_sorted_by_position(&self) -> Result<Vec<Display<'_>>> {
+        let mut displays = self.active_bounds()?;
+        displays.sort_by_key(|(_, bounds)| (bounds.top(), bounds.left()));
+
+        Ok(displays
+            .into_iter()
+            .map(|(display, _)| display)
+            .chain(
+                self.displays()
+                    .filter(|display| display.settings().is_none()),
+            )
+            .collect())
+    }
+
+    /// Returns the indices of the active displays in the order they physically sit, left-to-right, then top-to-bottom
+    /// Useful to present displays in a UI; inactive displays (without settings) are excluded.
+    pub fn display_order(&self) -> Result<Vec<usize>> {
+        let mut displays: Vec<_> = self
+            .active_bounds()?
+            .into_iter()
+            .map(|(display, bounds)| (bounds.left(), bounds.top(), display.index()))
+            .collect();
+        displays.sort_unstable();
+        Ok(displays.into_iter().map(|(_, _, index)| index).collect())
+    }
+
+    /// Returns the displays with settings together with the area they cover on the desktop
+    /// Fails if the settings of any of them are currently borrowed mutably
+    fn active_bounds(&self) -> Result<Vec<(Display<'_>, Rectangle)>> {
+        self.displays()
+            .filter_map(|display| match display.bounds() {
+                Ok(bounds) => bounds.map(|bounds| Ok((display, bounds))),
+                Err(err) => Some(Err(err)),
+            })
+            .collect()
+    }
+
     /// Returns display for the given `index`
-    pub fn get(&self, index: usize) -> Option<Display> {
+    pub fn get(&self, index: usize) -> Option<Display<'_>> {
         if index >= self.displays.len() {
             return None;
         }
@@ -103,67 +505,575 @@ impl DisplaySet {
         })
     }
 
+    /// Returns the display with the given `id`, if it is still part of this set
+    pub fn resolve(&self, id: &DisplayId) -> Option<Display<'_>> {
+        self.displays().find(|display| display.key() == id.as_str())
+    }
+
     /// Returns the primary display
-    pub fn primary(&self) -> Display {
+    pub fn primary(&self) -> Display<'_> {
         Display {
             index: self.primary_display.get(),
             display_set: self,
         }
     }
 
+    /// Calls `f` with the settings of the primary display, allowing to change them
+    /// Requires a call to `apply` and `refresh` afterwards
+    pub fn with_primary<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut DisplaySettings) -> R,
+    {
+        self.primary().with_settings_mut(f)
+    }
+
+    /// Returns the index of the primary display
+    pub fn primary_index(&self) -> usize {
+        self.primary_display.get()
+    }
+
+    /// Returns the smallest rectangle containing all active displays
+    pub fn bounding_box(&self) -> Result<Option<Rectangle>> {
+        Ok(self
+            .active_bounds()?
+            .into_iter()
+            .map(|(_, bounds)| bounds)
+            .reduce(|bounding_box, bounds| bounding_box.union(&bounds)))
+    }
+
+    /// Returns the number of pixels of all active displays combined, saturating at `u64::MAX`
+    pub fn total_pixels(&self) -> Result<u64> {
+        self.displays().try_fold(0u64, |total, display| {
+            Ok(total.saturating_add(display.pixel_count()?))
+        })
+    }
+
+    /// Returns a hash of the keys of the active displays, ignoring their settings and order
+    /// The fingerprint only changes when displays are connected or disconnected, e.g. to pick a profile for the current combination.
+    /// Uses FNV-1a, so it is stable across runs and versions of Rust.
+    pub fn topology_fingerprint(&self) -> u64 {
+        let mut keys: Vec<&str> = self
+            .iter_active()
+            .map(|display| self.displays[display.index].key.as_str())
+            .collect();
+        keys.sort_unstable();
+
+        keys.iter()
+            .flat_map(|key| key.bytes().chain([0]))
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    /// Returns the active display whose center is closest to the given `point`
+    pub fn nearest_display(&self, point: Position) -> Result<Option<Display<'_>>> {
+        Ok(self
+            .active_bounds()?
+            .into_iter()
+            .min_by_key(|(_, bounds)| bounds.center().manhattan_distance(&point))
+            .map(|(display, _)| display))
+    }
+
+    /// Returns pairs of active displays `(a, b)` that are separated by a gap
+    /// Each pair links a group of touching displays to the group of the primary display via its closest display.
+    /// `Rectangle::gap_to` returns how far `b` has to move to close the gap.
+    pub fn layout_gaps(&self) -> Result<Vec<(usize, usize)>> {
+        let bounds: Vec<(usize, Rectangle)> = self
+            .active_bounds()?
+            .into_iter()
+            .map(|(display, bounds)| (display.index(), bounds))
+            .collect();
+        let mut connected: Vec<bool> = bounds
+            .iter()
+            .map(|(index, _)| *index == self.primary_index())
+            .collect();
+        if !connected.iter().any(|connected| *connected) {
+            if let Some(first) = connected.first_mut() {
+                *first = true;
+            }
+        }
+
+        let mut gaps = Vec::new();
+        loop {
+            // grow the connected group by all displays touching it
+            let mut grown = true;
+            while grown {
+                grown = false;
+                for j in 0..bounds.len() {
+                    if !connected[j]
+                        && (0..bounds.len())
+                            .any(|i| connected[i] && bounds[i].1.touches(&bounds[j].1))
+                    {
+                        connected[j] = true;
+                        grown = true;
+                    }
+                }
+            }
+
+            // bridge the closest display that is still separated
+            let closest = (0..bounds.len())
+                .filter(|i| connected[*i])
+                .flat_map(|i| {
+                    (0..bounds.len())
+                        .filter(|j| !connected[*j])
+                        .map(move |j| (i, j))
+                })
+                .min_by_key(|(i, j)| bounds[*i].1.gap_length(&bounds[*j].1));
+
+            match closest {
+                Some((i, j)) => {
+                    gaps.push((bounds[i].0, bounds[j].0));
+                    connected[j] = true;
+                }
+                None => break,
+            }
+        }
+
+        Ok(gaps)
+    }
+
+    /// Moves displays whose edges are within `threshold` pixels of another display's edge, so the edges align exactly
+    /// The primary display stays in place and axes that are aligned already are left untouched, so snapping twice changes nothing.
+    /// Requires a call to `display_set.apply` and `refresh` afterwards
+    pub fn snap_to_edges(&self, threshold: i32) -> Result {
+        for display in self.displays() {
+            if display.is_primary() {
+                continue;
+            }
+            let bounds = match display.bounds()? {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+
+            let others: Vec<Rectangle> = self
+                .active_bounds()?
+                .into_iter()
+                .filter(|(other, _)| other.index() != display.index())
+                .map(|(_, bounds)| bounds)
+                .collect();
+
+            let dx = snap_offset(
+                others.iter().flat_map(|other| {
+                    [
+                        other.left().saturating_sub(bounds.left()),
+                        other.right().saturating_sub(bounds.right()),
+                        other.left().saturating_sub(bounds.right()),
+                        other.right().saturating_sub(bounds.left()),
+                    ]
+                }),
+                threshold,
+            );
+            let dy = snap_offset(
+                others.iter().flat_map(|other| {
+                    [
+                        other.top().saturating_sub(bounds.top()),
+                        other.bottom().saturating_sub(bounds.bottom()),
+                        other.top().saturating_sub(bounds.bottom()),
+                        other.bottom().saturating_sub(bounds.top()),
+                    ]
+                }),
+                threshold,
+            );
+
+            display.with_settings_mut(|settings| {
+                settings.position = settings.position + Position::new(dx, dy);
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the display with the given `index` by `dx` and `dy` pixels, leaving all other displays untouched
+    /// Combine it with `snap_to_edges` to align the display after dragging it in a UI.
+    /// Requires a call to `display_set.apply` and `refresh` afterwards
+    pub fn move_display_relative(&self, index: usize, dx: i32, dy: i32) -> Result {
+        let display = self.get(index).ok_or(DisplayError::NotFound(index))?;
+
+        display.with_settings_mut(|settings| {
+            settings.position = settings.position + Position::new(dx, dy);
+        })
+    }
+
     /// Sets the given `display` as the primary display
     /// Requires a call to `display_set.apply` and `commit_changes` afterwards
     pub fn set_primary(&self, display: &Display) -> Result {
-        let index = display.index;
-        let new_primary = &self.displays[index];
+        self.set_primary_and_arrange(display.index, Arrangement::Keep)
+    }
+
+    /// Sets the display with the given `index` as the primary display and rearranges all active displays in a single pass
+    /// Requires a call to `display_set.apply` and `refresh` afterwards
+    pub fn set_primary_and_arrange(&self, index: usize, arrangement: Arrangement) -> Result {
+        let display = self
+            .displays
+            .get(index)
+            .ok_or(DisplayError::NotFound(index))?;
 
-        if !new_primary.active {
+        if !display.active {
             return Err(DisplayError::PrimaryDisplay);
         }
 
-        let old_position = new_primary
-            .settings
-            .as_ref()
-            .ok_or_else(|| DisplayError::NoSettings(new_primary.name.to_string()))?
-            .borrow()
-            .position;
+        self.update_layout(|settings, indices| {
+            // the index is in the list, because only active displays can become primary
+            let new_primary = indices.iter().position(|i| *i == index).unwrap();
+            arrange(settings, arrangement, new_primary);
+        })?;
 
-        // move all other displays to new position (because we set a new origin in the next step)
         for (i, display) in self.displays.iter().enumerate() {
-            if display.active && i != index {
+            display.primary.set(i == index);
+        }
+        self.primary_display.set(index);
+
+        Ok(())
+    }
+
+    /// Moves all active displays, so the primary display is at the origin `(0, 0)` again, e.g. after another tool broke this assumption
+    /// The relative arrangement of the displays stays the same.
+    /// Requires a call to `display_set.apply` and `refresh` afterwards
+    pub fn ensure_primary_at_origin(&self) -> Result {
+        let primary = self.primary_index();
+        if !self.displays[primary].active {
+            return Err(DisplayError::PrimaryDisplay);
+        }
+
+        self.update_layout(|settings, indices| {
+            // the primary is in the list, because it is active
+            let primary = indices.iter().position(|i| *i == primary).unwrap();
+            recompute_origin(settings, primary);
+        })
+    }
+
+    /// Returns the indices and mutably borrowed settings of all active displays
+    /// Fails without changing anything if any of the settings are borrowed already
+    fn active_settings(&self) -> Result<Vec<(usize, RefMut<'_, DisplaySettings>)>> {
+        self.displays
+            .iter()
+            .enumerate()
+            .filter(|(_, display)| display.active)
+            .map(|(index, display)| {
                 let settings = display
                     .settings
                     .as_ref()
                     .ok_or_else(|| DisplayError::NoSettings(display.name.to_string()))?;
-                let pos = settings.borrow().position;
-                settings.borrow_mut().position = -old_position + pos;
+                let settings = settings
+                    .try_borrow_mut()
+                    .map_err(|_| DisplayError::Busy(display.name.to_string()))?;
+
+                Ok((index, settings))
+            })
+            .collect()
+    }
+
+    /// Calls `f` with copies of the settings of all active displays (and their indices) and writes them back afterwards
+    /// This lets the pure functions of the `layout` module work on a display set
+    fn update_layout<F>(&self, f: F) -> Result
+    where
+        F: FnOnce(&mut [DisplaySettings], &[usize]),
+    {
+        let mut active = self.active_settings()?;
+        let indices: Vec<usize> = active.iter().map(|(index, _)| *index).collect();
+        let mut settings: Vec<DisplaySettings> =
+            active.iter().map(|(_, settings)| **settings).collect();
+
+        f(&mut settings, &indices);
+
+        for ((_, borrowed), settings) in active.iter_mut().zip(settings) {
+            **borrowed = settings;
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the settings of any display differ from the ones queried or last applied
+    pub fn has_changes(&self) -> bool {
+        self.displays().any(|display| display.has_changes())
+    }
+
+    /// Sets all changes on the displays
+    /// Only displays and fields that changed since they were queried or last applied are written, see `apply_force`
+    pub fn apply(&self) -> Result {
+        self.apply_changes(ApplyOptions::default()).map(|_| ())
+    }
+
+    /// Sets all changes on the displays like `apply`, e.g. without persisting them, see `ApplyOptions`
+    pub fn apply_with_options(&self, options: ApplyOptions) -> Result {
+        self.apply_changes(options).map(|_| ())
+    }
+
+    /// Sets all changes on the displays like `apply`, retrying up to `attempts` times in total if Windows fails transiently
+    /// `DISP_CHANGE_FAILED` is considered transient, as drivers return it while a monitor is being connected; all other failures are returned right away.
+    /// Waits a little longer before each retry and returns `DisplayError::RebootRequired` if Windows reports `DISP_CHANGE_RESTART`.
+    /// Requires a call to `refresh` afterwards
+    pub fn apply_with_retry(&self, attempts: u32) -> Result {
+        let mut attempt = 1;
+        loop {
+            match self.apply_changes(ApplyOptions::default()) {
+                Ok(true) => return Err(DisplayError::RebootRequired),
+                Err(err) if attempt < attempts && is_transient(&err) => {
+                    log::debug!(
+                        "Applying failed in attempt {} of {}, retrying",
+                        attempt,
+                        attempts
+                    );
+                    thread::sleep(RETRY_DELAY * attempt);
+                    attempt += 1;
+                }
+                result => return result.map(|_| ()),
+            }
+        }
+    }
+
+    /// Writes the changed fields of all displays that changed
+    /// Returns whether any display requires a restart (`DISP_CHANGE_RESTART`) for the changes to take effect
+    /// Fails without writing anything if the settings of an active display are borrowed mutably.
+    fn apply_changes(&self, options: ApplyOptions) -> Result<bool> {
+        self.ensure_live()?;
+        if !self.has_changes() {
+            log::debug!("No display settings changed, skipping apply");
+            return Ok(false);
+        }
+
+        let mut restart = false;
+        for display in self.active_unborrowed()? {
+            let fields = display.dirty_fields();
+            if !fields.is_empty() {
+                restart |= self.write_display(display.index, fields, options)?;
+                self.mark_applied(display.index);
             }
         }
 
-        // the new primary is the new origin
-        let new_primary_mut = &self.displays[index];
-        let new_settings = new_primary_mut
+        Ok(restart)
+    }
+
+    /// Sets the settings of all active displays, even if they did not change
+    /// Fails without writing anything if the settings of an active display are borrowed mutably.
+    pub fn apply_force(&self) -> Result {
+        self.ensure_live()?;
+
+        for display in self.active_unborrowed()? {
+            self.write_display(display.index, DirtyFields::all(), ApplyOptions::default())?;
+            self.mark_applied(display.index);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the active displays, or `DisplayError::Busy` if the settings of any of them are borrowed mutably
+    /// Lets writes check all borrows up front, so they never leave the set half-applied.
+    fn active_unborrowed(&self) -> Result<Vec<Display<'_>>> {
+        self.iter_active()
+            .map(|display| display.with_settings(|_| ()).map(|_| display))
+            .collect()
+    }
+
+    /// Sets all changes on the displays and refreshes the screen to commit them
+    /// Does nothing if no settings changed since they were queried or last applied
+    /// Use `apply` and `refresh` separately to batch multiple applies before a single refresh
+    pub fn apply_and_refresh(&self) -> Result {
+        self.ensure_live()?;
+        if !self.has_changes() {
+            log::debug!("No display settings changed, skipping apply and refresh");
+            return Ok(());
+        }
+
+        self.apply()?;
+        self.refresh_displays()
+    }
+
+    /// Sets all changes, refreshes the screen and queries the settings again to check that they took effect
+    /// Returns `(index, requested, actual)` for every display whose settings differ, e.g. because Windows silently picked the nearest mode.
+    /// Afterwards the set holds the actual settings, so the caller can decide whether to set the requested ones again or revert.
+    pub fn apply_verified(&self) -> Result<Vec<(usize, DisplaySettings, DisplaySettings)>> {
+        self.ensure_live()?;
+
+        let requested = self
+            .iter_active()
+            .map(|display| Ok((display.with_settings(|settings| *settings)?, display)))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.apply_and_refresh()?;
+
+        let mut mismatches = Vec::new();
+        for (requested, display) in requested {
+            display.refresh_settings()?;
+            let actual = display.with_settings(|settings| *settings)?;
+            if actual != requested {
+                log::warn!(
+                    "Display {} was set to {} instead of {}",
+                    display.name(),
+                    actual,
+                    requested
+                );
+                mismatches.push((display.index, requested, actual));
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Copies the settings and the primary display of this set onto the matching displays of the `live` set and applies them
+    /// Displays are matched by their device key; displays of `live` that are not part of this set are left untouched.
+    /// Fails without changing `live` if an active display of this set has no active counterpart.
+    /// Requires a call to `refresh` afterwards
+    pub fn apply_to(&self, live: &DisplaySet) -> Result {
+        let mut matches = Vec::new();
+        for display in self.iter_active() {
+            let target = live
+                .resolve(&display.id())
+                .filter(|target| target.settings().is_some())
+                .ok_or_else(|| DisplayError::NoMatch(display.key().to_string()))?;
+            matches.push((display, target));
+        }
+
+        for (display, target) in &matches {
+            let settings = display.with_settings(|settings| *settings)?;
+            target.with_settings_mut(|target| *target = settings)?;
+        }
+        if let Some((_, target)) = matches
+            .iter()
+            .find(|(display, target)| display.is_primary() && !target.is_primary())
+        {
+            live.set_primary(target)?;
+        }
+
+        live.apply()
+    }
+
+    /// Fails if this set was created from a snapshot and is not backed by live displays
+    pub(crate) fn ensure_live(&self) -> Result {
+        if self.detached {
+            return Err(DisplayError::Detached);
+        }
+
+        Ok(())
+    }
+
+    /// Whether this set was created from fake displays, see `from_mock`
+    #[cfg(feature = "topology")]
+    pub(crate) fn is_mock(&self) -> bool {
+        self.mock
+    }
+
+    /// Takes over which settings were applied from `other`, a copy of this set that was applied elsewhere
+    #[cfg(feature = "async")]
+    pub(crate) fn copy_applied_from(&self, other: &DisplaySet) {
+        for (applied, other) in self.applied.iter().zip(&other.applied) {
+            applied.set(other.get());
+        }
+    }
+
+    /// Remembers the current settings of the display with the given `index` as applied
+    fn mark_applied(&self, index: usize) {
+        let settings = self.displays[index]
             .settings
             .as_ref()
-            .ok_or_else(|| DisplayError::NoSettings(new_primary_mut.name.to_string()))?;
+            .and_then(|settings| settings.try_borrow().ok().map(|settings| *settings));
+        self.applied[index].set(settings);
+    }
 
-        new_settings.borrow_mut().position = Position::new(0, 0);
+    /// Sets the changes of the display with the given `index` only, leaving all other displays untouched
+    /// Requires a call to `refresh` afterwards
+    pub fn apply_display(&self, index: usize) -> Result {
+        self.ensure_live()?;
+        let display = self
+            .displays
+            .get(index)
+            .ok_or(DisplayError::NotFound(index))?;
 
-        self.primary_display.set(index);
+        if !display.active {
+            return Err(DisplayError::NoSettings(display.name.to_string()));
+        }
+
+        self.write_display(index, DirtyFields::all(), ApplyOptions::default())?;
+        self.mark_applied(index);
 
         Ok(())
     }
 
-    /// Sets all changes on the displays
-    pub fn apply(&self) -> Result {
-        for display in self.displays.iter() {
-            if display.active {
-                display.apply()?;
-            }
+    /// Writes the given `fields` of the display with the given `index`, returning whether a restart is required
+    /// Mock sets only check that the settings can be read, as there is no device to write to.
+    fn write_display(
+        &self,
+        index: usize,
+        fields: DirtyFields,
+        options: ApplyOptions,
+    ) -> Result<bool> {
+        if self.mock {
+            let display = Display {
+                index,
+                display_set: self,
+            };
+            display.with_settings(|_| ())?;
+            self.mock_writes
+                .borrow_mut()
+                .push((index, self.displays[index].primary.get()));
+            return Ok(false);
+        }
+
+        write_settings(&self.displays[index], fields, options)
+    }
+
+    /// Refreshes the screen to commit the changes of this set, see `refresh`
+    fn refresh_displays(&self) -> Result {
+        self.ensure_live()?;
+        if self.mock {
+            return Ok(());
+        }
+
+        refresh()
+    }
+
+    /// Turns on all monitors, e.g. after `sleep`
+    /// Broadcasts `SC_MONITORPOWER` to all top-level windows, so it acts on every monitor at once
+    pub fn wake(&self) -> Result {
+        self.ensure_live()?;
+        if self.mock {
+            return Ok(());
+        }
+
+        send_monitor_power(MONITOR_POWER_ON)
+    }
+
+    /// Turns off all monitors until `wake` is called or there is user input
+    pub fn sleep(&self) -> Result {
+        self.ensure_live()?;
+        if self.mock {
+            return Ok(());
+        }
+
+        send_monitor_power(MONITOR_POWER_OFF)
+    }
+
+    /// Queries the displays again and replaces the contents of this set, including the primary display
+    /// Use this to pick up changes made by other applications while keeping the same `DisplaySet`
+    /// Mock sets discard their pending changes instead, as there are no other applications changing them.
+    pub fn reload(&mut self) -> Result {
+        if self.mock {
+            return self
+                .displays()
+                .filter(|display| display.settings().is_some())
+                .try_for_each(|display| display.refresh_settings());
         }
 
+        *self = query_displays()?;
+
         Ok(())
     }
+
+    /// Returns a read-only copy of the displays that can be shared across threads
+    /// Fails if the settings of any display are currently borrowed mutably
+    pub fn snapshot(&self) -> Result<DisplaySnapshot> {
+        Ok(DisplaySnapshot {
+            displays: self
+                .displays
+                .iter()
+                .map(|display| {
+                    DisplaySnapshotEntry::try_from(display)
+                        .map_err(|_| DisplayError::Busy(display.name.to_string()))
+                })
+                .collect::<Result<_>>()?,
+            primary_index: self.primary_display.get(),
+        })
+    }
 }
 
 impl fmt::Display for DisplaySet {
@@ -180,15 +1090,20 @@ impl fmt::Display for DisplaySet {
 }
 
 /// Returns a list of all displays.
+#[cfg(windows)]
 pub fn query_displays() -> Result<DisplaySet> {
     let mut result = Vec::<DisplayProperties>::new();
 
     let mut dev_num: usize = 0;
-    let mut display_device = DISPLAY_DEVICE::default();
+    let mut display_device = winsafe::DISPLAY_DEVICE::default();
 
     loop {
-        let is_good =
-            EnumDisplayDevices(None, dev_num as u32, &mut display_device, co::EDD::NoValue)?;
+        let is_good = winsafe::EnumDisplayDevices(
+            None,
+            dev_num as u32,
+            &mut display_device,
+            co::EDD::NoValue,
+        )?;
 
         if !is_good {
             break;
@@ -206,17 +1121,634 @@ pub fn query_displays() -> Result<DisplaySet> {
         dev_num += 1; // advance to next display device
     }
 
-    Ok(DisplaySet {
-        displays: result,
-        primary_display: Cell::new(0),
-    })
+    Ok(DisplaySet::new(result))
+}
+
+/// Always fails, as displays can only be queried on Windows
+/// Allows crates that depend on displayz to compile on other platforms and handle the error at runtime.
+#[cfg(not(windows))]
+pub fn query_displays() -> Result<DisplaySet> {
+    Err(DisplayError::Unsupported(
+        "displays can only be queried on Windows",
+    ))
+}
+
+/// Returns the smallest of the `offsets` within `threshold`, or `0` if one of them is aligned already
+fn snap_offset(offsets: impl Iterator<Item = i32>, threshold: i32) -> i32 {
+    let offsets: Vec<i32> = offsets
+        .filter(|offset| i64::from(offset.unsigned_abs()) <= i64::from(threshold))
+        .collect();
+    if offsets.contains(&0) {
+        return 0;
+    }
+
+    offsets
+        .into_iter()
+        .min_by_key(|offset| offset.unsigned_abs())
+        .unwrap_or(0)
+}
+
+/// Caches the result of `query_displays` until it is invalidated
+#[derive(Debug, Default)]
+pub struct DisplayCache {
+    /// The cached display set, if the displays were queried already
+    display_set: OnceCell<DisplaySet>,
+}
+
+impl DisplayCache {
+    /// Creates an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached display set or queries the displays if the cache is empty
+    pub fn get_or_query(&self) -> Result<&DisplaySet> {
+        if let Some(display_set) = self.display_set.get() {
+            return Ok(display_set);
+        }
+
+        let display_set = query_displays()?;
+        Ok(self.display_set.get_or_init(|| display_set))
+    }
+
+    /// Drops the cached display set, so the next `get_or_query` queries the displays again
+    /// Takes `&mut self`, so no display borrowed from the cache can outlive the invalidation
+    pub fn invalidate(&mut self) {
+        self.display_set.take();
+    }
+
+    /// Sets all changes on the cached displays and invalidates the cache afterwards
+    /// Requires a call to `refresh` afterwards
+    pub fn apply(&mut self) -> Result {
+        if let Some(display_set) = self.display_set.get() {
+            display_set.apply()?;
+        }
+        self.invalidate();
+
+        Ok(())
+    }
 }
 
 /// Refreshes the screen to apply the changes
+#[cfg(windows)]
 pub fn refresh() -> Result {
-    let result = winsafe::ChangeDisplaySettingsEx(None, None, winsafe::co::CDS::DYNAMICALLY);
+    let result = change_display_settings(None, None, winsafe::co::CDS::DYNAMICALLY);
     match result {
         Ok(_) => Ok(()),
         Err(err) => Err(DisplayError::FailedToCommit(err)),
     }
 }
+
+/// Always fails, as displays can only be refreshed on Windows
+#[cfg(not(windows))]
+pub fn refresh() -> Result {
+    Err(DisplayError::Unsupported(
+        "displays can only be refreshed on Windows",
+    ))
+}
+
+/// Refreshes the display with the given `name`, falling back to refreshing all displays if Windows rejects the call
+#[cfg(windows)]
+fn refresh_display(name: &str) -> Result {
+    match change_display_settings(Some(name), None, co::CDS::DYNAMICALLY) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            log::debug!(
+                "Refreshing display {} failed with {}, refreshing all displays instead",
+                name,
+                err
+            );
+            refresh()
+        }
+    }
+}
+
+/// Always fails, see `refresh`
+#[cfg(not(windows))]
+fn refresh_display(_name: &str) -> Result {
+    refresh()
+}
+
+/// Writes the given `fields` of the settings of a display, returning whether a restart is required
+#[cfg(windows)]
+fn write_settings(
+    properties: &DisplayProperties,
+    fields: DirtyFields,
+    options: ApplyOptions,
+) -> Result<bool> {
+    Ok(properties.apply_fields(fields, options)? == co::DISP_CHANGE::RESTART)
+}
+
+/// Whether applying failed with `DISP_CHANGE_FAILED`, which drivers return while a monitor is being connected
+#[cfg(windows)]
+fn is_transient(err: &DisplayError) -> bool {
+    matches!(
+        err,
+        DisplayError::Properties(DisplayPropertiesError::ApplyFailed(co::DISP_CHANGE::FAILED))
+    )
+}
+
+/// Never true, as display settings can only be written on Windows
+#[cfg(not(windows))]
+fn is_transient(_err: &DisplayError) -> bool {
+    false
+}
+
+/// Always fails, as display settings can only be written on Windows
+#[cfg(not(windows))]
+fn write_settings(
+    _properties: &DisplayProperties,
+    _fields: DirtyFields,
+    _options: ApplyOptions,
+) -> Result<bool> {
+    Err(DisplayError::Unsupported(
+        "display settings can only be applied on Windows",
+    ))
+}
+
+/// Offset basis of the 64 bit FNV-1a hash used by `DisplaySet::topology_fingerprint`
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+/// Prime of the 64 bit FNV-1a hash used by `DisplaySet::topology_fingerprint`
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// The delay before the first retry of `DisplaySet::apply_with_retry`, growing linearly with each attempt
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// `SC_MONITORPOWER` parameter that turns the monitors on
+const MONITOR_POWER_ON: isize = -1;
+/// `SC_MONITORPOWER` parameter that turns the monitors off
+const MONITOR_POWER_OFF: isize = 2;
+
+/// Sends `SC_MONITORPOWER` with the given power `state` to all top-level windows
+/// Uses `SendMessage`, so it returns after all windows have processed the message
+#[cfg(windows)]
+fn send_monitor_power(state: isize) -> Result {
+    use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{SendMessageW, SC_MONITORPOWER, WM_SYSCOMMAND};
+
+    /// `HWND_BROADCAST`, which the `windows` crate does not define
+    const HWND_BROADCAST: HWND = HWND(0xffff);
+
+    unsafe {
+        SendMessageW(
+            HWND_BROADCAST,
+            WM_SYSCOMMAND,
+            WPARAM(SC_MONITORPOWER as usize),
+            LPARAM(state),
+        );
+    }
+
+    Ok(())
+}
+
+/// Always fails, as monitors can only be turned on and off on Windows
+#[cfg(not(windows))]
+fn send_monitor_power(_state: isize) -> Result {
+    Err(DisplayError::Unsupported(
+        "monitors can only be turned on and off on Windows",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(x: i32, y: i32, width: u32, height: u32) -> DisplaySettings {
+        DisplaySettings {
+            position: Position::new(x, y),
+            resolution: Resolution::new(width, height),
+            ..Default::default()
+        }
+    }
+
+    fn position(display_set: &DisplaySet, index: usize) -> Position {
+        display_set
+            .get(index)
+            .unwrap()
+            .with_settings(|settings| settings.position)
+            .unwrap()
+    }
+
+    #[test]
+    fn mock_set_applies_without_windows() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 0, 1920, 1080)),
+        ]);
+        display_set.move_display_relative(1, 10, 0).unwrap();
+        assert!(display_set.has_changes());
+
+        display_set.apply_and_refresh().unwrap();
+        assert!(!display_set.has_changes());
+        assert_eq!(position(&display_set, 1), Position::new(1930, 0));
+    }
+
+    #[test]
+    fn mock_set_reverts_to_applied_settings() {
+        let mut display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 0, 1920, 1080)),
+        ]);
+        display_set.move_display_relative(1, 10, 0).unwrap();
+        display_set.get(1).unwrap().discard_changes().unwrap();
+        assert_eq!(position(&display_set, 1), Position::new(1920, 0));
+
+        display_set.move_display_relative(1, 0, 10).unwrap();
+        display_set.reload().unwrap();
+        assert_eq!(position(&display_set, 1), Position::new(1920, 0));
+        assert!(!display_set.has_changes());
+    }
+
+    #[test]
+    fn detached_set_rejects_writes_without_changes() {
+        let display_set =
+            DisplaySet::from_mock(vec![
+                MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary()
+            ]);
+        let detached = DisplaySet::from_snapshot(display_set.snapshot().unwrap());
+        assert!(!detached.has_changes());
+
+        assert!(matches!(detached.apply(), Err(DisplayError::Detached)));
+        assert!(matches!(
+            detached.apply_force(),
+            Err(DisplayError::Detached)
+        ));
+        assert!(matches!(
+            detached.apply_and_refresh(),
+            Err(DisplayError::Detached)
+        ));
+        assert!(matches!(
+            detached.apply_display(0),
+            Err(DisplayError::Detached)
+        ));
+        assert!(matches!(
+            detached.primary().refresh(),
+            Err(DisplayError::Detached)
+        ));
+    }
+
+    #[test]
+    fn layout_queries_fail_while_settings_are_borrowed() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1925, 3, 1920, 1080)),
+        ]);
+
+        display_set
+            .get(1)
+            .unwrap()
+            .with_settings_mut(|_| {
+                assert!(matches!(
+                    display_set.snap_to_edges(10),
+                    Err(DisplayError::Busy(_))
+                ));
+                assert!(matches!(display_set.snapshot(), Err(DisplayError::Busy(_))));
+                assert!(display_set.display_order().is_err());
+                assert!(display_set.layout_gaps().is_err());
+                assert!(display_set.bounding_box().is_err());
+                assert!(display_set.total_pixels().is_err());
+                assert!(display_set.nearest_display(Position::default()).is_err());
+            })
+            .unwrap();
+
+        display_set.snap_to_edges(10).unwrap();
+        assert_eq!(position(&display_set, 1), Position::new(1920, 0));
+    }
+
+    #[test]
+    fn dirty_fields_track_changes_until_applied() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 0, 1920, 1080)),
+            MockDisplay::inactive("c"),
+        ]);
+        let display = display_set.get(1).unwrap();
+        assert!(!display_set.has_changes());
+
+        display_set.move_display_relative(1, 10, 0).unwrap();
+        assert_eq!(
+            display.dirty_fields(),
+            DirtyFields {
+                position: true,
+                ..Default::default()
+            }
+        );
+        assert!(!display_set.get(0).unwrap().has_changes());
+        assert!(!display_set.get(2).unwrap().has_changes());
+
+        display.apply().unwrap();
+        assert!(!display_set.has_changes());
+
+        // the last applied position counts, not the queried one
+        display_set.move_display_relative(1, -10, 0).unwrap();
+        assert!(display.has_changes());
+        display_set.apply().unwrap();
+        assert!(!display_set.has_changes());
+    }
+
+    #[test]
+    fn dirty_fields_are_all_set_while_borrowed() {
+        let display_set =
+            DisplaySet::from_mock(vec![
+                MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary()
+            ]);
+        let display = display_set.primary();
+
+        display
+            .with_settings_mut(|_| assert_eq!(display.dirty_fields(), DirtyFields::all()))
+            .unwrap();
+        assert!(!display.has_changes());
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn set_custom_resolution_marks_settings_as_applied() {
+        let display_set =
+            DisplaySet::from_mock(vec![
+                MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary()
+            ]);
+        let display = display_set.primary();
+
+        display
+            .set_custom_resolution(Resolution::new(2560, 1080), 60)
+            .unwrap();
+        assert!(!display.has_changes());
+        display.discard_changes().unwrap();
+        assert_eq!(
+            display
+                .with_settings(|settings| settings.resolution)
+                .unwrap(),
+            Resolution::new(2560, 1080)
+        );
+    }
+
+    #[test]
+    fn snap_to_edges_does_not_overflow_at_i32_bounds() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(i32::MIN, i32::MIN, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(i32::MAX - 1915, i32::MAX - 5, 1920, 1080)),
+        ]);
+
+        display_set.snap_to_edges(i32::MAX).unwrap();
+        assert_eq!(
+            position(&display_set, 1),
+            Position::new(i32::MAX - 1915, i32::MAX - 5)
+        );
+        assert_eq!(
+            snap_offset([i32::MIN, i32::MAX].into_iter(), i32::MAX),
+            i32::MAX
+        );
+    }
+
+    #[test]
+    fn primary_falls_back_to_the_display_at_the_origin() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::inactive("a"),
+            MockDisplay::new("b", settings(-1920, 0, 1920, 1080)),
+            MockDisplay::new("c", settings(0, 0, 1920, 1080)),
+        ]);
+        assert_eq!(display_set.primary_index(), 2);
+    }
+
+    #[test]
+    fn primary_falls_back_to_the_first_active_display() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::inactive("a"),
+            MockDisplay::new("b", settings(-1920, 0, 1920, 1080)),
+            MockDisplay::new("c", settings(1920, 0, 1920, 1080)),
+        ]);
+        assert_eq!(display_set.primary_index(), 1);
+
+        let display_set =
+            DisplaySet::from_mock(vec![MockDisplay::inactive("a"), MockDisplay::inactive("b")]);
+        assert_eq!(display_set.primary_index(), 0);
+    }
+
+    #[test]
+    fn ensure_primary_at_origin_moves_all_active_displays() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(100, 50, 1920, 1080)).primary(),
+            MockDisplay::inactive("b"),
+            MockDisplay::new("c", settings(-1820, 50, 1920, 1080)),
+        ]);
+
+        display_set.ensure_primary_at_origin().unwrap();
+        assert_eq!(position(&display_set, 0), Position::new(0, 0));
+        assert_eq!(position(&display_set, 2), Position::new(-1920, 0));
+        assert!(display_set.has_changes());
+    }
+
+    #[test]
+    fn ensure_primary_at_origin_rejects_an_inactive_primary() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::inactive("a").primary(),
+            MockDisplay::new("b", settings(100, 50, 1920, 1080)),
+        ]);
+
+        assert!(matches!(
+            display_set.ensure_primary_at_origin(),
+            Err(DisplayError::PrimaryDisplay)
+        ));
+        assert_eq!(position(&display_set, 1), Position::new(100, 50));
+    }
+
+    #[test]
+    fn set_orientation_swaps_the_resolution_only_between_landscape_and_portrait() {
+        let display_set =
+            DisplaySet::from_mock(vec![
+                MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary()
+            ]);
+        let display = display_set.get(0).unwrap();
+        let resolution = || {
+            display
+                .with_settings(|settings| settings.resolution)
+                .unwrap()
+        };
+
+        display.set_orientation(Orientation::Portrait).unwrap();
+        assert_eq!(resolution(), Resolution::new(1080, 1920));
+
+        display
+            .set_orientation(Orientation::PortraitFlipped)
+            .unwrap();
+        assert_eq!(resolution(), Resolution::new(1080, 1920));
+
+        display.set_orientation(Orientation::Landscape).unwrap();
+        assert_eq!(resolution(), Resolution::new(1920, 1080));
+
+        display
+            .set_orientation(Orientation::LandscapeFlipped)
+            .unwrap();
+        assert_eq!(resolution(), Resolution::new(1920, 1080));
+        assert_eq!(
+            display
+                .with_settings(|settings| settings.orientation)
+                .unwrap(),
+            Orientation::LandscapeFlipped
+        );
+    }
+
+    #[test]
+    fn layout_gaps_are_empty_for_touching_displays() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 0, 1920, 1080)),
+            MockDisplay::new("c", settings(0, 1080, 1920, 1080)),
+        ]);
+        assert_eq!(display_set.layout_gaps().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn layout_gaps_bridge_a_detached_display_to_the_primary_group() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)),
+            MockDisplay::new("b", settings(2000, 0, 1920, 1080)).primary(),
+            MockDisplay::new("c", settings(3920, 0, 1920, 1080)),
+        ]);
+        assert_eq!(display_set.layout_gaps().unwrap(), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn layout_gaps_include_diagonal_and_corner_adjacent_displays() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 1080, 1920, 1080)),
+            MockDisplay::new("c", settings(-2000, -1200, 1920, 1080)),
+        ]);
+        assert_eq!(display_set.layout_gaps().unwrap(), vec![(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn layout_gaps_ignore_inactive_displays() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::inactive("b"),
+            MockDisplay::new("c", settings(1920, 0, 1920, 1080)),
+            MockDisplay::new("d", settings(3940, 0, 1920, 1080)),
+        ]);
+        assert_eq!(display_set.layout_gaps().unwrap(), vec![(2, 3)]);
+    }
+
+    #[test]
+    fn apply_skips_the_write_without_changes() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 0, 1920, 1080)),
+        ]);
+
+        display_set.apply().unwrap();
+        display_set.apply_and_refresh().unwrap();
+        assert_eq!(display_set.mock_writes.borrow().len(), 0);
+
+        display_set.move_display_relative(1, 10, 0).unwrap();
+        display_set.apply().unwrap();
+        display_set.apply().unwrap();
+        assert_eq!(*display_set.mock_writes.borrow(), vec![(1, false)]);
+
+        display_set.apply_force().unwrap();
+        assert_eq!(display_set.mock_writes.borrow().len(), 3);
+    }
+
+    #[test]
+    fn make_primary_writes_the_primary_flag_on_the_new_primary() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 0, 1920, 1080)),
+        ]);
+
+        display_set.get(1).unwrap().make_primary().unwrap();
+
+        // both displays move, but only the new primary is written with `CDS_SET_PRIMARY`
+        assert_eq!(
+            *display_set.mock_writes.borrow(),
+            vec![(0, false), (1, true)]
+        );
+        assert_eq!(position(&display_set, 0), Position::new(-1920, 0));
+        assert_eq!(position(&display_set, 1), Position::new(0, 0));
+        assert!(!display_set.has_changes());
+    }
+
+    #[test]
+    fn apply_to_moves_the_primary_flag_of_the_live_set() {
+        let edited = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(-1920, 0, 1920, 1080)),
+            MockDisplay::new("b", settings(0, 0, 1920, 1080)).primary(),
+        ]);
+        let offline = DisplaySet::from_snapshot(edited.snapshot().unwrap());
+        let live = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 0, 1920, 1080)),
+        ]);
+
+        offline.apply_to(&live).unwrap();
+
+        assert_eq!(live.primary_index(), 1);
+        assert!(!live.displays[0].primary.get());
+        assert!(live.displays[1].primary.get());
+        assert_eq!(*live.mock_writes.borrow(), vec![(0, false), (1, true)]);
+        assert_eq!(position(&live, 0), Position::new(-1920, 0));
+        assert_eq!(position(&live, 1), Position::new(0, 0));
+    }
+
+    #[test]
+    fn writes_fail_without_writing_anything_while_settings_are_borrowed() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 0, 1920, 1080)),
+        ]);
+        display_set.move_display_relative(0, 10, 0).unwrap();
+
+        display_set
+            .get(1)
+            .unwrap()
+            .with_settings_mut(|_| {
+                assert!(matches!(display_set.apply(), Err(DisplayError::Busy(_))));
+                assert!(matches!(
+                    display_set.apply_force(),
+                    Err(DisplayError::Busy(_))
+                ));
+                assert!(matches!(
+                    display_set.apply_display(1),
+                    Err(DisplayError::Busy(_))
+                ));
+                assert!(matches!(
+                    display_set.get(1).unwrap().set_primary(),
+                    Err(DisplayError::Busy(_))
+                ));
+            })
+            .unwrap();
+
+        // the display before the busy one was not written either
+        assert!(display_set.mock_writes.borrow().is_empty());
+        assert_eq!(
+            display_set.get(0).unwrap().dirty_fields(),
+            DirtyFields {
+                position: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(display_set.primary_index(), 0);
+
+        display_set.apply().unwrap();
+        assert_eq!(*display_set.mock_writes.borrow(), vec![(0, true)]);
+    }
+
+    #[test]
+    fn total_pixels_saturates_instead_of_overflowing() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, 1920, 1080)).primary(),
+            MockDisplay::new("b", settings(1920, 0, 2560, 1440)),
+            MockDisplay::inactive("c"),
+        ]);
+        assert_eq!(
+            display_set.total_pixels().unwrap(),
+            1920 * 1080 + 2560 * 1440
+        );
+
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0, u32::MAX, u32::MAX)).primary(),
+            MockDisplay::new("b", settings(0, 0, u32::MAX, u32::MAX)),
+        ]);
+        assert_eq!(display_set.total_pixels().unwrap(), u64::MAX);
+    }
+}
@@ -0,0 +1,42 @@
+use windows::Win32::Graphics::Dxgi::{CreateDXGIFactory1, IDXGIFactory1, DXGI_ERROR_NOT_FOUND};
+
+use crate::{Display, DisplayError};
+
+type Result<T = ()> = std::result::Result<T, DisplayError>;
+
+impl Display<'_> {
+    /// Returns the `(adapter_index, output_index)` of the DXGI output that shows this display
+    /// The output is found by matching the name of the display against `IDXGIOutput::GetDesc().DeviceName`
+    pub fn dxgi_output_index(&self) -> Result<(u32, u32)> {
+        let factory: IDXGIFactory1 = unsafe { CreateDXGIFactory1()? };
+
+        for adapter_index in 0.. {
+            let adapter = match unsafe { factory.EnumAdapters1(adapter_index) } {
+                Ok(adapter) => adapter,
+                Err(err) if err.code() == DXGI_ERROR_NOT_FOUND => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            for output_index in 0.. {
+                let output = match unsafe { adapter.EnumOutputs(output_index) } {
+                    Ok(output) => output,
+                    Err(err) if err.code() == DXGI_ERROR_NOT_FOUND => break,
+                    Err(err) => return Err(err.into()),
+                };
+
+                let desc = unsafe { output.GetDesc()? };
+                let name_len = desc
+                    .DeviceName
+                    .iter()
+                    .position(|c| *c == 0)
+                    .unwrap_or(desc.DeviceName.len());
+
+                if String::from_utf16_lossy(&desc.DeviceName[..name_len]) == self.name() {
+                    return Ok((adapter_index, output_index));
+                }
+            }
+        }
+
+        Err(DisplayError::NoDxgiOutput(self.name().to_string()))
+    }
+}
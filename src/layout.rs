@@ -0,0 +1,160 @@
+use crate::properties::{DisplaySettings, Position};
+
+/// Describes how displays are arranged relative to each other
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Arrangement {
+    /// Side by side from left to right, aligned at the top
+    Horizontal,
+    /// Stacked from top to bottom, aligned at the left
+    Vertical,
+    /// The current relative arrangement
+    Keep,
+}
+
+/// Moves all displays, so the display at index `new_primary` ends up at the origin `(0, 0)`
+/// The relative arrangement of the displays stays the same. Panics if `new_primary` is out of bounds.
+pub fn recompute_origin(displays: &mut [DisplaySettings], new_primary: usize) {
+    let origin = displays[new_primary].position;
+
+    for display in displays.iter_mut() {
+        display.position = display.position - origin;
+    }
+}
+
+/// Places the displays side by side without gaps, keeping their current left-to-right order
+/// The leftmost display ends up at the origin `(0, 0)`
+pub fn arrange_horizontally(displays: &mut [DisplaySettings]) {
+    let mut order: Vec<usize> = (0..displays.len()).collect();
+    order.sort_by_key(|&index| {
+        let bounds = displays[index].bounds();
+        (bounds.left(), bounds.top())
+    });
+
+    let mut x = 0;
+    for index in order {
+        displays[index].position = Position::new(x, 0);
+        x = x.saturating_add_unsigned(displays[index].resolution.width);
+    }
+}
+
+/// Stacks the displays without gaps, keeping their current top-to-bottom order
+/// The topmost display ends up at the origin `(0, 0)`
+pub fn arrange_vertically(displays: &mut [DisplaySettings]) {
+    let mut order: Vec<usize> = (0..displays.len()).collect();
+    order.sort_by_key(|&index| {
+        let bounds = displays[index].bounds();
+        (bounds.top(), bounds.left())
+    });
+
+    let mut y = 0;
+    for index in order {
+        displays[index].position = Position::new(0, y);
+        y = y.saturating_add_unsigned(displays[index].resolution.height);
+    }
+}
+
+/// Arranges the displays according to `arrangement` and moves them, so the display at index `new_primary` ends up at the origin `(0, 0)`
+/// Panics if `new_primary` is out of bounds.
+pub fn arrange(displays: &mut [DisplaySettings], arrangement: Arrangement, new_primary: usize) {
+    match arrangement {
+        Arrangement::Horizontal => arrange_horizontally(displays),
+        Arrangement::Vertical => arrange_vertically(displays),
+        Arrangement::Keep => {}
+    }
+
+    recompute_origin(displays, new_primary);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockDisplay;
+    use crate::{DisplayError, DisplaySet, Resolution};
+
+    fn settings(x: i32, y: i32) -> DisplaySettings {
+        DisplaySettings {
+            position: Position::new(x, y),
+            resolution: Resolution::new(1920, 1080),
+            ..Default::default()
+        }
+    }
+
+    fn positions(display_set: &DisplaySet) -> Vec<Option<Position>> {
+        display_set
+            .displays()
+            .map(|display| display.with_settings(|settings| settings.position).ok())
+            .collect()
+    }
+
+    #[test]
+    fn set_primary_rebases_a_primary_at_a_negative_position() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0)).primary(),
+            MockDisplay::new("b", settings(-1920, -200)),
+            MockDisplay::new("c", settings(1920, 0)),
+        ]);
+
+        display_set.get(1).unwrap().set_primary().unwrap();
+
+        assert_eq!(
+            positions(&display_set),
+            vec![
+                Some(Position::new(1920, 200)),
+                Some(Position::new(0, 0)),
+                Some(Position::new(3840, 200)),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_primary_moves_the_primary_index_and_flag() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0)).primary(),
+            MockDisplay::new("b", settings(1920, 0)),
+            MockDisplay::new("c", settings(3840, 0)),
+        ]);
+
+        display_set.get(2).unwrap().set_primary().unwrap();
+
+        assert_eq!(display_set.primary_index(), 2);
+        // the flag decides which display is written with `CDS_SET_PRIMARY`
+        assert_eq!(
+            display_set
+                .displays()
+                .map(|display| display.properties().primary.get())
+                .collect::<Vec<_>>(),
+            vec![false, false, true]
+        );
+        assert_eq!(positions(&display_set)[0], Some(Position::new(-3840, 0)));
+    }
+
+    #[test]
+    fn set_primary_rejects_inactive_displays() {
+        let display_set = DisplaySet::from_mock(vec![
+            MockDisplay::new("a", settings(0, 0)).primary(),
+            MockDisplay::inactive("b"),
+            MockDisplay::new("c", settings(1920, 0)),
+        ]);
+
+        assert!(matches!(
+            display_set.get(1).unwrap().set_primary(),
+            Err(DisplayError::PrimaryDisplay)
+        ));
+        assert_eq!(display_set.primary_index(), 0);
+        assert!(display_set.get(0).unwrap().properties().primary.get());
+        assert!(!display_set.get(1).unwrap().properties().primary.get());
+        assert!(!display_set.has_changes());
+    }
+
+    #[test]
+    fn recompute_origin_keeps_the_arrangement() {
+        let mut displays = [settings(0, 0), settings(-1920, -200), settings(1920, 0)];
+
+        recompute_origin(&mut displays, 1);
+
+        assert_eq!(displays[0].position, Position::new(1920, 200));
+        assert_eq!(displays[1].position, Position::new(0, 0));
+        assert_eq!(displays[2].position, Position::new(3840, 200));
+    }
+}
@@ -1,9 +1,41 @@
 //! A library to interact with the Windows API for display settings.
 //!
 //! This library provides an abstraction around some `winuser.h` calls relevant for modifying display settings.
+//!
+//! Displays are enumerated with `EnumDisplayDevices` and their settings are read with `EnumDisplaySettings`.
+//! Changes are written per display with `ChangeDisplaySettingsEx` (`CDS_UPDATEREGISTRY | CDS_NORESET`)
+//! and take effect once `refresh` calls `ChangeDisplaySettingsEx` without a display.
 
+#[cfg(feature = "capture")]
+mod capture;
+#[cfg(feature = "serde")]
+mod config;
+mod diagnostics;
 mod display;
+#[cfg(feature = "dxgi")]
+mod dxgi;
+mod layout;
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+#[cfg(feature = "async")]
+mod nonblocking;
 mod properties;
+mod query;
+mod snapshot;
+#[cfg(feature = "topology")]
+mod topology;
 
+#[cfg(feature = "capture")]
+pub use capture::*;
+#[cfg(feature = "serde")]
+pub use config::*;
+pub use diagnostics::*;
 pub use display::*;
+pub use layout::*;
+#[cfg(feature = "mock")]
+pub use mock::*;
 pub use properties::*;
+pub use query::*;
+pub use snapshot::*;
+#[cfg(feature = "topology")]
+pub use topology::*;
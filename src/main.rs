@@ -1,11 +1,20 @@
 //! The CLI interface for displayz
 //!
 //! Use the `--help` flag to see the available options.
-use std::cell::RefMut;
+//!
+//! Exits with one of the following codes:
+//! - `0`: success
+//! - `1`: any other error
+//! - `2`: the display was not found
+//! - `3`: applying the settings failed
+//! - `4`: the display has no settings
+use std::{path::PathBuf, process};
 
-use color_eyre::eyre::{eyre, Result};
+use color_eyre::eyre::{bail, Report, Result};
 use displayz::{
-    query_displays, refresh, DisplaySettings, FixedOutput, Orientation, Position, Resolution,
+    query_displays, raw_display_info, refresh, ConfigMatches, Display, DisplayConfig, DisplayError,
+    DisplayPropertiesError, DisplaySettings, FixedOutput, Orientation, PartialDisplaySettings,
+    Position, Resolution,
 };
 use structopt::{clap::ArgGroup, StructOpt};
 
@@ -22,6 +31,9 @@ struct Opts {
     /// Output debug info
     #[structopt(short, long, global = true)]
     verbose: bool,
+    /// Print the result as JSON
+    #[structopt(long, global = true)]
+    json: bool,
 }
 
 /// Subcommands to select the mode of operatiom
@@ -50,6 +62,31 @@ enum SubCommands {
         #[structopt(flatten)]
         properties: PropertiesOpt,
     },
+    /// Changes resolution, position and orientation of a display with a specified id at once
+    #[structopt(alias = "sm")]
+    SetMode {
+        /// The id of the display
+        #[structopt(short, long)]
+        id: usize,
+        /// The new mode of the display
+        #[structopt(
+            long_help = "The new mode of the display. Expected format: `<width>x<height>[,<x>,<y>][,<orientation>[,<fixed output>]]`, e.g. `1920x1080,0,0,Default,Stretch`. Omitted parts keep their current value."
+        )]
+        spec: PartialDisplaySettings,
+    },
+    /// Prints the raw display devices and their settings as reported by Windows, e.g. for bug reports
+    ///
+    /// Add `--verbose` to include the settings saved in the registry.
+    Debug,
+    /// Applies the desired state of the displays described in a config file
+    Apply {
+        /// The config file to apply, TOML if it ends in `.toml` and JSON otherwise
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// Fail if any entry of the config has no matching display, not only required ones
+        #[structopt(long)]
+        strict: bool,
+    },
 }
 
 /// Describes the properties that can be changed on a display
@@ -90,8 +127,40 @@ struct PropertiesOpt {
     fixed_output: Option<FixedOutput>,
 }
 
+/// Exit code when the display was not found
+const EXIT_NOT_FOUND: i32 = 2;
+/// Exit code when applying the settings failed
+#[cfg(windows)]
+const EXIT_APPLY_FAILED: i32 = 3;
+/// Exit code when the display has no settings
+const EXIT_NO_SETTINGS: i32 = 4;
+
 /// Entry point for `displayz`.
-fn main() -> Result<()> {
+fn main() {
+    if let Err(report) = run() {
+        eprintln!("Error: {:?}", report);
+        process::exit(exit_code(&report));
+    }
+}
+
+/// Maps an error to the exit code of the CLI
+fn exit_code(report: &Report) -> i32 {
+    match report.downcast_ref::<DisplayError>() {
+        Some(DisplayError::NotFound(_)) | Some(DisplayError::NoMatch(_)) => EXIT_NOT_FOUND,
+        #[cfg(windows)]
+        Some(DisplayError::FailedToCommit(_))
+        | Some(DisplayError::Properties(DisplayPropertiesError::ApplyFailed(_)))
+        | Some(DisplayError::Properties(DisplayPropertiesError::TestFailed(_))) => {
+            EXIT_APPLY_FAILED
+        }
+        Some(DisplayError::NoSettings(_))
+        | Some(DisplayError::Properties(DisplayPropertiesError::NoSettings(_))) => EXIT_NO_SETTINGS,
+        _ => 1,
+    }
+}
+
+/// Runs the CLI
+fn run() -> Result<()> {
     color_eyre::install()?;
 
     let opts = Opts::from_args();
@@ -107,56 +176,152 @@ fn main() -> Result<()> {
 
     log::debug!("Parsed Opts:\n{:#?}", opts);
 
+    // dumps the raw values, so it has to work even if the displays can't be queried
+    if let SubCommands::Debug = opts.cmd {
+        let dump = raw_display_info(opts.verbose)?;
+        if opts.json {
+            println!("{}", serde_json::json!({ "raw_display_info": dump }));
+        } else {
+            print!("{}", dump);
+        }
+        return Ok(());
+    }
+
     let display_set = query_displays()?;
     log::debug!("Discovered displays:\n{}", display_set);
 
     match opts.cmd {
         SubCommands::SetPrimary { id } => {
-            let display = display_set
-                .get(id)
-                .ok_or_else(|| eyre!("Display with id {} not found", id))?;
+            let display = display_set.get(id).ok_or(DisplayError::NotFound(id))?;
 
             display.set_primary()?;
 
-            display_set.apply()?;
-            refresh()?;
-            log::info!("Display settings changed");
+            let changed = display_set.has_changes();
+            display_set.apply_and_refresh()?;
+            report_changes(opts.json, id, if changed { &["primary"] } else { &[] });
         }
         SubCommands::Primary { properties } => {
-            let display = display_set.primary();
-
-            if let Some(settings) = display.settings() {
-                let mut settings = settings.borrow_mut();
-                set_properties(&properties, &mut settings);
-            } else {
-                Err(eyre!("Primary display has no settings"))?;
-            }
+            display_set.with_primary(|settings| set_properties(&properties, settings))?;
 
-            display.apply()?;
-            refresh()?;
-            log::info!("Display settings changed");
+            let applied = apply_changed_fields(&display_set.primary())?;
+            report_changes(opts.json, display_set.primary_index(), &applied);
         }
         SubCommands::Properties { id, properties } => {
-            let display = display_set
-                .get(id)
-                .ok_or_else(|| eyre!("Display with id {} not found", id))?;
-
-            if let Some(settings) = display.settings() {
-                let mut settings = settings.borrow_mut();
-                set_properties(&properties, &mut settings)
-            } else {
-                Err(eyre!("Display has no settings"))?;
+            let display = display_set.get(id).ok_or(DisplayError::NotFound(id))?;
+            display.with_settings_mut(|settings| set_properties(&properties, settings))?;
+
+            let applied = apply_changed_fields(&display)?;
+            report_changes(opts.json, id, &applied);
+        }
+        SubCommands::SetMode { id, spec } => {
+            let display = display_set.get(id).ok_or(DisplayError::NotFound(id))?;
+            // parts missing from the spec keep their current value
+            let settings = display.with_settings(|current| {
+                let mut settings = *current;
+                spec.apply_to(&mut settings);
+                settings
+            })?;
+
+            if !display.test_settings(&settings)? {
+                bail!(
+                    "The mode {} requires a restart and was not applied",
+                    settings
+                );
             }
+            display.with_settings_mut(|current| *current = settings)?;
 
-            display.apply()?;
-            refresh()?;
-            log::info!("Display settings changed");
+            let applied = apply_changed_fields(&display)?;
+            log::info!("New settings of display {}: {}", id, settings);
+            report_changes(opts.json, id, &applied);
         }
+        SubCommands::Apply { file, strict } => {
+            let config = DisplayConfig::from_file(&file)?;
+            let matches = config.apply_to(&display_set, strict)?;
+
+            let changed = display_set.has_changes();
+            display_set.apply_and_refresh()?;
+            report_config(opts.json, changed, &config, &matches);
+        }
+        SubCommands::Debug => unreachable!("handled before querying the displays"),
     }
 
     Ok(())
 }
 
+/// Applies and refreshes the changes of the `display`, returning the names of the fields that changed
+/// Does nothing if no field changed.
+fn apply_changed_fields(display: &Display) -> Result<Vec<&'static str>> {
+    let fields = display.dirty_fields();
+    let applied: Vec<&'static str> = [
+        ("position", fields.position),
+        ("resolution", fields.resolution),
+        ("orientation", fields.orientation),
+        ("fixed_output", fields.fixed_output),
+    ]
+    .into_iter()
+    .filter_map(|(name, changed)| changed.then_some(name))
+    .collect();
+
+    if !applied.is_empty() {
+        display.apply()?;
+        refresh()?;
+    }
+
+    Ok(applied)
+}
+
+/// Reports the applied changes, either as log line or as JSON object
+/// Nothing changed if `applied` is empty.
+fn report_changes(json: bool, display: usize, applied: &[&str]) {
+    let changed = !applied.is_empty();
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "changed": changed, "display": display, "applied": applied })
+        );
+    } else if changed {
+        log::info!("Display settings changed");
+    } else {
+        log::info!("Display settings are unchanged");
+    }
+}
+
+/// Reports which entries of a config were matched to a display, either as log lines or as JSON object
+fn report_config(json: bool, changed: bool, config: &DisplayConfig, matches: &ConfigMatches) {
+    if json {
+        let matched: Vec<_> = matches
+            .matched
+            .iter()
+            .map(|(entry, display)| {
+                serde_json::json!({ "entry": config.displays[*entry].describe(), "display": display })
+            })
+            .collect();
+        let unmatched: Vec<_> = matches
+            .unmatched
+            .iter()
+            .map(|entry| config.displays[*entry].describe())
+            .collect();
+        println!(
+            "{}",
+            serde_json::json!({ "changed": changed, "matched": matched, "unmatched": unmatched })
+        );
+    } else {
+        for (entry, display) in &matches.matched {
+            log::info!(
+                "Applied {} to display {}",
+                config.displays[*entry].describe(),
+                display
+            );
+        }
+        for entry in &matches.unmatched {
+            log::warn!(
+                "No display found for {}",
+                config.displays[*entry].describe()
+            );
+        }
+    }
+}
+
 /// Sets a specific settings from the given properties
 macro_rules! assign_if_ok {
     ($properties:expr, $settings:expr, $name:ident) => {
@@ -167,7 +332,7 @@ macro_rules! assign_if_ok {
 }
 
 /// Sets all available properties
-fn set_properties(properties: &PropertiesOpt, settings: &mut RefMut<DisplaySettings>) {
+fn set_properties(properties: &PropertiesOpt, settings: &mut DisplaySettings) {
     assign_if_ok!(properties, settings, position);
     assign_if_ok!(properties, settings, resolution);
     assign_if_ok!(properties, settings, orientation);
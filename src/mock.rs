@@ -0,0 +1,53 @@
+use std::cell::{Cell, RefCell};
+
+use crate::properties::{DisplayProperties, DisplaySettings};
+
+/// A fake display to build a `DisplaySet` from, see `DisplaySet::from_mock`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockDisplay {
+    /// The name of the display, also used as its key
+    pub name: String,
+    /// Whether the display is the primary display
+    pub primary: bool,
+    /// The settings of the display; displays without settings are inactive
+    pub settings: Option<DisplaySettings>,
+}
+
+impl MockDisplay {
+    /// Creates an active display with the given settings
+    pub fn new(name: &str, settings: DisplaySettings) -> Self {
+        Self {
+            name: name.to_string(),
+            primary: false,
+            settings: Some(settings),
+        }
+    }
+
+    /// Creates an inactive display
+    pub fn inactive(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Marks the display as primary display
+    pub fn primary(mut self) -> Self {
+        self.primary = true;
+        self
+    }
+}
+
+impl From<MockDisplay> for DisplayProperties {
+    fn from(display: MockDisplay) -> Self {
+        DisplayProperties {
+            key: display.name.clone(),
+            string: "Mock display".to_string(),
+            name: display.name,
+            monitor_id: String::new(),
+            active: display.settings.is_some(),
+            primary: Cell::new(display.primary),
+            settings: display.settings.map(RefCell::new),
+        }
+    }
+}
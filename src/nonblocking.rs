@@ -0,0 +1,52 @@
+use std::future::Future;
+
+use crate::{DisplayError, DisplaySet};
+
+type Result<T = ()> = std::result::Result<T, DisplayError>;
+
+impl DisplaySet {
+    /// Sets all changes on the displays without blocking the calling thread, see `apply`
+    /// The changes are applied on a copy of this set on tokio's blocking thread pool, so this requires a running tokio runtime.
+    /// Afterwards this set knows which settings were applied, just like after `apply`.
+    /// Win32 display calls should generally come from a thread with a message pump; prefer `apply` where blocking is acceptable.
+    /// Requires a call to `refresh` afterwards
+    pub fn apply_async(&self) -> impl Future<Output = Result> + '_ {
+        let display_set = self.clone();
+
+        async move {
+            let (display_set, result) = tokio::task::spawn_blocking(move || {
+                let result = display_set.apply();
+                (display_set, result)
+            })
+            .await
+            .map_err(DisplayError::Join)?;
+            self.copy_applied_from(&display_set);
+
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockDisplay;
+    use crate::{DisplaySet, DisplaySettings, Position};
+
+    #[test]
+    fn apply_async_marks_settings_as_applied() {
+        let display_set =
+            DisplaySet::from_mock(vec![
+                MockDisplay::new("a", DisplaySettings::default()).primary()
+            ]);
+        display_set
+            .with_primary(|settings| settings.position = Position::new(10, 0))
+            .unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(display_set.apply_async()).unwrap();
+
+        assert!(!display_set.has_changes());
+    }
+}
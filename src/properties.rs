@@ -1,9 +1,10 @@
 use core::fmt;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::ops::{Add, Neg, Sub};
 use std::str::FromStr;
 
 use thiserror::Error;
+#[cfg(windows)]
 use winsafe::{co, prelude::NativeBitflag, GmidxEnum, DISPLAY_DEVICE, POINT};
 
 /// Error type for the display module
@@ -11,28 +12,57 @@ use winsafe::{co, prelude::NativeBitflag, GmidxEnum, DISPLAY_DEVICE, POINT};
 pub enum DisplayPropertiesError {
     #[error("Display {0} has no settings")]
     NoSettings(String),
+    #[cfg(windows)]
     #[error("Error when calling the Windows API")]
     WinAPI(#[from] co::ERROR),
+    #[cfg(windows)]
     #[error("Apply failed, returned flags: {0}")]
     ApplyFailed(co::DISP_CHANGE),
     #[error("Invalid orientation: {0}")]
     InvalidOrientation(String),
     #[error("Invalid fixed output: {0}")]
     InvalidFixedOutput(String),
+    #[error("Settings of display {0} are already borrowed")]
+    Busy(String),
+    #[error("Not supported on this platform: {0}")]
+    Unsupported(&'static str),
+    #[cfg(windows)]
+    #[error("Settings were rejected: {}", describe_disp_change(*.0))]
+    TestFailed(co::DISP_CHANGE),
+}
+
+/// Describes why `ChangeDisplaySettingsEx` returned the given `DISP_CHANGE` code
+#[cfg(windows)]
+fn describe_disp_change(code: co::DISP_CHANGE) -> String {
+    match code {
+        co::DISP_CHANGE::SUCCESSFUL => "the settings are valid".to_string(),
+        co::DISP_CHANGE::RESTART => "the computer must be restarted".to_string(),
+        co::DISP_CHANGE::FAILED => "the display driver failed the graphics mode".to_string(),
+        co::DISP_CHANGE::BADMODE => "the graphics mode is not supported".to_string(),
+        co::DISP_CHANGE::NOTUPDATED => "unable to write the settings to the registry".to_string(),
+        co::DISP_CHANGE::BADFLAGS => "an invalid set of flags was passed".to_string(),
+        co::DISP_CHANGE::BADPARAM => "an invalid parameter was passed".to_string(),
+        co::DISP_CHANGE::BADDUALVIEW => "the system is DualView capable".to_string(),
+        _ => format!("unknown result {}", code),
+    }
 }
 
 type Result<T = ()> = std::result::Result<T, DisplayPropertiesError>;
 
 /// Contains the properties of a display
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DisplayProperties {
     pub name: String,
 
     pub string: String,
     pub key: String,
+    /// The device interface path of the monitor, e.g. `\\?\DISPLAY#GSM5B08#5&1f3ab4a&0&UID4352#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}`
+    /// Empty if no monitor is attached.
+    pub monitor_id: String,
 
     pub active: bool,
-    pub primary: bool,
+    pub primary: Cell<bool>,
 
     pub settings: Option<RefCell<DisplaySettings>>,
 }
@@ -42,13 +72,18 @@ impl fmt::Display for DisplayProperties {
         write!(
             f,
             "Display {{ key: {}, name: {}, string: {}, active: {}, primary: {} }}",
-            self.key, self.name, self.string, self.active, self.primary
+            self.key,
+            self.name,
+            self.string,
+            self.active,
+            self.primary.get()
         )
     }
 }
 
 /// Contains the settings of a display
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DisplaySettings {
     pub position: Position,
     pub resolution: Resolution,
@@ -56,8 +91,256 @@ pub struct DisplaySettings {
     pub fixed_output: FixedOutput,
 }
 
+impl Default for DisplaySettings {
+    /// 1920x1080 in landscape at the origin, a mode virtually every display supports
+    fn default() -> Self {
+        Self {
+            position: Position::default(),
+            resolution: Resolution::new(1920, 1080),
+            orientation: Orientation::Landscape,
+            fixed_output: FixedOutput::Default,
+        }
+    }
+}
+
+impl DisplaySettings {
+    /// Returns the area the display covers on the desktop
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle::new(self.position, self.resolution)
+    }
+
+    /// Returns the fields that differ from the `other` settings
+    pub fn changed_fields(&self, other: &DisplaySettings) -> DirtyFields {
+        DirtyFields {
+            position: self.position != other.position,
+            resolution: self.resolution != other.resolution,
+            orientation: self.orientation != other.orientation,
+            fixed_output: self.fixed_output != other.fixed_output,
+        }
+    }
+}
+
+/// Options that change how settings are applied
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ApplyOptions {
+    /// Whether the settings are saved in the registry and take effect with `refresh`, which is the default
+    /// Settings that are not persisted take effect immediately and must not be followed by `refresh`, which restores the saved settings.
+    /// They last until the saved settings are restored, e.g. by a restart.
+    pub persist: bool,
+}
+
+impl Default for ApplyOptions {
+    fn default() -> Self {
+        Self { persist: true }
+    }
+}
+
+/// Marks which fields of `DisplaySettings` changed and are written when applying
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DirtyFields {
+    pub position: bool,
+    pub resolution: bool,
+    pub orientation: bool,
+    pub fixed_output: bool,
+}
+
+impl DirtyFields {
+    /// Marks all fields as changed
+    pub fn all() -> Self {
+        Self {
+            position: true,
+            resolution: true,
+            orientation: true,
+            fixed_output: true,
+        }
+    }
+
+    /// Returns whether no field changed
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for DisplaySettings {
+    /// Writes the settings in the format parsed by `DisplaySettings::from_str`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{}",
+            self.resolution,
+            self.position.x(),
+            self.position.y(),
+            self.orientation,
+            self.fixed_output
+        )
+    }
+}
+
+/// Errors that occur while parsing display settings from a string
+#[derive(Error, Debug)]
+pub enum ParseDisplaySettingsError {
+    #[error("Invalid resolution. Expected format: <width>x<height>")]
+    Resolution(#[source] ParsePositionError),
+    #[error("Invalid position. Expected format: <x>,<y>")]
+    Position(#[source] std::num::ParseIntError),
+    #[error("Invalid orientation")]
+    Orientation(#[from] ParseOrientationError),
+    #[error("Invalid fixed output mode")]
+    FixedOutput(#[from] ParseFixedOutputError),
+    #[error("Refresh rates are not supported, use `Display::set_custom_resolution` instead")]
+    Frequency,
+    #[error("Wrong number of parts ({0}). Expected format: <width>x<height>[,<x>,<y>][,<orientation>[,<fixed output>]]")]
+    PartCount(usize),
+}
+
+impl FromStr for DisplaySettings {
+    type Err = ParseDisplaySettingsError;
+
+    /// Parses settings in the format `<width>x<height>[,<x>,<y>][,<orientation>[,<fixed output>]]`, e.g. `1920x1080,0,0,landscape,stretch`
+    /// Omitted parts are taken from `DisplaySettings::default`, see `PartialDisplaySettings` to keep the current ones instead.
+    /// Refresh rates (`1920x1080@144`) are not part of the format, as the settings don't hold one; see `Display::set_custom_resolution`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut settings = DisplaySettings::default();
+        s.parse::<PartialDisplaySettings>()?.apply_to(&mut settings);
+
+        Ok(settings)
+    }
+}
+
+/// Display settings where each field is optional, so a config only overwrites the fields it contains
+/// E.g. `{ "resolution": { "width": 1920, "height": 1080 } }` changes the resolution but keeps the position.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartialDisplaySettings {
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub position: Option<Position>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub resolution: Option<Resolution>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub orientation: Option<Orientation>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub fixed_output: Option<FixedOutput>,
+}
+
+impl PartialDisplaySettings {
+    /// Overwrites the fields of `settings` that are set in this struct
+    pub fn apply_to(&self, settings: &mut DisplaySettings) {
+        if let Some(position) = self.position {
+            settings.position = position;
+        }
+        if let Some(resolution) = self.resolution {
+            settings.resolution = resolution;
+        }
+        if let Some(orientation) = self.orientation {
+            settings.orientation = orientation;
+        }
+        if let Some(fixed_output) = self.fixed_output {
+            settings.fixed_output = fixed_output;
+        }
+    }
+}
+
+impl From<DisplaySettings> for PartialDisplaySettings {
+    fn from(settings: DisplaySettings) -> Self {
+        Self {
+            position: Some(settings.position),
+            resolution: Some(settings.resolution),
+            orientation: Some(settings.orientation),
+            fixed_output: Some(settings.fixed_output),
+        }
+    }
+}
+
+impl FromStr for PartialDisplaySettings {
+    type Err = ParseDisplaySettingsError;
+
+    /// Parses settings in the format of `DisplaySettings::from_str`, leaving the omitted parts unset
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut parts = s.split(',').map(str::trim);
+        let part_count = s.split(',').count();
+        let mut settings = PartialDisplaySettings::default();
+
+        // split always yields at least one part
+        let resolution = parts.next().unwrap_or_default();
+        if resolution.contains('@') {
+            return Err(ParseDisplaySettingsError::Frequency);
+        }
+        settings.resolution = Some(
+            resolution
+                .parse()
+                .map_err(ParseDisplaySettingsError::Resolution)?,
+        );
+
+        let rest: Vec<&str> = parts.collect();
+        // orientations and fixed output modes are never numbers, so a number starts the position
+        let (position, rest) = match rest.as_slice() {
+            [x, y, rest @ ..] if x.parse::<i32>().is_ok() || y.parse::<i32>().is_ok() => {
+                (Some((x, y)), rest)
+            }
+            rest => (None, rest),
+        };
+        // an empty or numeric part here means a coordinate is missing or there is one too many
+        if rest
+            .iter()
+            .any(|part| part.is_empty() || part.parse::<i32>().is_ok())
+        {
+            return Err(ParseDisplaySettingsError::PartCount(part_count));
+        }
+        let (orientation, fixed_output) = match rest {
+            [] => (None, None),
+            [orientation] => (Some(orientation), None),
+            [orientation, fixed_output] => (Some(orientation), Some(fixed_output)),
+            _ => return Err(ParseDisplaySettingsError::PartCount(part_count)),
+        };
+
+        if let Some((x, y)) = position {
+            settings.position = Some(Position::new(
+                x.parse().map_err(ParseDisplaySettingsError::Position)?,
+                y.parse().map_err(ParseDisplaySettingsError::Position)?,
+            ));
+        }
+        if let Some(orientation) = orientation {
+            settings.orientation = Some(orientation.parse()?);
+        }
+        if let Some(fixed_output) = fixed_output {
+            settings.fixed_output = Some(fixed_output.parse()?);
+        }
+
+        Ok(settings)
+    }
+}
+
+impl DisplayProperties {
+    /// Returns the PnP device instance path of the monitor, e.g. `DISPLAY\GSM5B08\5&1f3ab4a&0&UID4352`
+    /// It is taken from the device interface path in `monitor_id`, as the device key only points to the adapter's `Control\Video` registry key.
+    /// Returns `None` if no monitor is attached.
+    pub fn device_instance_path(&self) -> Option<String> {
+        let path = self.monitor_id.strip_prefix(r"\\?\")?;
+        // the interface class GUID follows the last `#`
+        let (path, _class) = path.rsplit_once('#')?;
+
+        (!path.is_empty()).then(|| path.replace('#', "\\"))
+    }
+}
+
+#[cfg(windows)]
 impl DisplayProperties {
     /// Create a display properties struct from a winsafe display
+    /// The current settings are fetched from Windows if the display is active
     pub fn from_winsafe(device: &DISPLAY_DEVICE) -> Result<DisplayProperties> {
         let active = device.StateFlags.has(co::DISPLAY_DEVICE::ACTIVE);
         let settings = if active {
@@ -70,12 +353,27 @@ impl DisplayProperties {
             name: device.DeviceName(),
             string: device.DeviceString(),
             key: device.DeviceKey(),
+            monitor_id: Self::fetch_monitor_id(&device.DeviceName()),
             active,
-            primary: device.StateFlags.has(co::DISPLAY_DEVICE::PRIMARY_DEVICE),
+            primary: Cell::new(device.StateFlags.has(co::DISPLAY_DEVICE::PRIMARY_DEVICE)),
             settings,
         })
     }
 
+    /// Fetch the device interface path of the first monitor attached to a display, or an empty string if there is none
+    fn fetch_monitor_id(name: &str) -> String {
+        let mut monitor = DISPLAY_DEVICE::default();
+        match winsafe::EnumDisplayDevices(
+            Some(name),
+            0,
+            &mut monitor,
+            co::EDD::GET_DEVICE_INTERFACE_NAME,
+        ) {
+            Ok(true) => monitor.DeviceID(),
+            _ => String::new(),
+        }
+    }
+
     /// Fetch the settings of a display
     fn fetch_settings(name: &str) -> Result<DisplaySettings> {
         let mut devmode = winsafe::DEVMODE::default();
@@ -85,28 +383,103 @@ impl DisplayProperties {
             &mut devmode,
         )?;
 
+        let position = devmode.dmPosition();
         Ok(DisplaySettings {
-            position: Position(devmode.dmPosition()),
+            position: Position::new(position.x, position.y),
             resolution: Resolution::new(devmode.dmPelsWidth, devmode.dmPelsHeight),
             orientation: Orientation::from_winsafe(devmode.dmDisplayOrientation())?,
             fixed_output: FixedOutput::from_winsafe(devmode.dmDisplayFixedOutput())?,
         })
     }
 
+    /// Re-fetch the settings of the display from Windows, replacing the cached ones
+    pub fn refresh_settings(&self) -> Result {
+        let mut settings = self
+            .settings
+            .as_ref()
+            .ok_or_else(|| DisplayPropertiesError::NoSettings(self.name.to_string()))?
+            .try_borrow_mut()
+            .map_err(|_| DisplayPropertiesError::Busy(self.name.to_string()))?;
+
+        *settings = Self::fetch_settings(&self.name)?;
+
+        Ok(())
+    }
+
     /// Apply the settings of the display
     pub fn apply(&self) -> Result {
-        if self.settings.is_none() {
-            return Err(DisplayPropertiesError::NoSettings(self.name.to_string()));
-        }
-        let settings = self.settings.as_ref().unwrap().borrow(); // safe, because we just checked it
+        self.apply_fields(DirtyFields::all(), ApplyOptions::default())
+            .map(|_| ())
+    }
 
-        let mut flags =
-            winsafe::co::CDS::UPDATEREGISTRY | winsafe::co::CDS::NORESET | winsafe::co::CDS::GLOBAL;
+    /// Apply only the given `fields` of the settings, leaving the others as they are
+    /// Returns the result of Windows, which is `DISP_CHANGE_RESTART` if the computer must be restarted for the settings to take effect
+    pub fn apply_fields(
+        &self,
+        fields: DirtyFields,
+        options: ApplyOptions,
+    ) -> Result<co::DISP_CHANGE> {
+        let settings = self
+            .settings
+            .as_ref()
+            .ok_or_else(|| DisplayPropertiesError::NoSettings(self.name.to_string()))?
+            .try_borrow()
+            .map_err(|_| DisplayPropertiesError::Busy(self.name.to_string()))?;
 
-        if self.primary {
-            flags |= winsafe::co::CDS::SET_PRIMARY;
+        let mut devmode = winsafe::DEVMODE::default();
+        if fields.position {
+            devmode.set_position(settings.position);
+        }
+        if fields.resolution {
+            devmode.set_resolution(settings.resolution);
+        }
+        if fields.orientation {
+            devmode.set_orientation(settings.orientation);
         }
+        if fields.fixed_output {
+            devmode.set_fixed_output(settings.fixed_output);
+        }
+
+        self.change_settings(&mut devmode, self.apply_flags(options))
+            .map_err(DisplayPropertiesError::ApplyFailed)
+    }
+
+    /// Applies a resolution and refresh rate (in Hz) that the driver accepts but does not necessarily enumerate
+    /// The mode is tested with `CDS_TEST` first and only written if Windows accepts it; returns the result of that test
+    /// Requires a call to `refresh` afterwards
+    pub fn apply_custom_resolution(
+        &self,
+        resolution: Resolution,
+        frequency: u32,
+    ) -> Result<co::DISP_CHANGE> {
+        let mut settings = self
+            .settings
+            .as_ref()
+            .ok_or_else(|| DisplayPropertiesError::NoSettings(self.name.to_string()))?
+            .try_borrow_mut()
+            .map_err(|_| DisplayPropertiesError::Busy(self.name.to_string()))?;
+
+        let mut devmode = winsafe::DEVMODE::from_display_settings(
+            settings.position,
+            settings.orientation,
+            settings.fixed_output,
+            resolution,
+        );
+        devmode.set_frequency(frequency);
+
+        let tested = self
+            .change_settings(&mut devmode, co::CDS::TEST)
+            .map_err(DisplayPropertiesError::ApplyFailed)?;
+        self.change_settings(&mut devmode, self.apply_flags(ApplyOptions::default()))
+            .map_err(DisplayPropertiesError::ApplyFailed)?;
+        settings.resolution = resolution;
+
+        Ok(tested)
+    }
 
+    /// Checks whether Windows would accept the given `settings` for this display, without changing anything
+    /// Returns `false` if they are valid but require a restart
+    pub fn test_settings(&self, settings: &DisplaySettings) -> Result<bool> {
         let mut devmode = winsafe::DEVMODE::from_display_settings(
             settings.position,
             settings.orientation,
@@ -114,21 +487,107 @@ impl DisplayProperties {
             settings.resolution,
         );
 
-        let result = winsafe::ChangeDisplaySettingsEx(Some(&self.name), Some(&mut devmode), flags);
-        // use into_ok_or_err as soon it is stable
-        match result {
-            Ok(_) => Ok(()),
-            Err(err) => Err(DisplayPropertiesError::ApplyFailed(err)),
+        match self.change_settings(&mut devmode, co::CDS::TEST) {
+            Ok(co::DISP_CHANGE::SUCCESSFUL) => Ok(true),
+            Ok(_) => Ok(false),
+            Err(err) => Err(DisplayPropertiesError::TestFailed(err)),
         }
     }
+
+    /// Returns the flags used to write the settings of this display
+    /// Persisted settings are only saved and take effect with `refresh`, others take effect immediately
+    fn apply_flags(&self, options: ApplyOptions) -> co::CDS {
+        let mut flags = if options.persist {
+            winsafe::co::CDS::UPDATEREGISTRY | winsafe::co::CDS::NORESET | winsafe::co::CDS::GLOBAL
+        } else {
+            winsafe::co::CDS::DYNAMICALLY
+        };
+
+        if self.primary.get() {
+            flags |= winsafe::co::CDS::SET_PRIMARY;
+        }
+
+        flags
+    }
+
+    /// Passes the `devmode` to `ChangeDisplaySettingsEx` for this display, logging it beforehand
+    fn change_settings(
+        &self,
+        devmode: &mut winsafe::DEVMODE,
+        flags: co::CDS,
+    ) -> std::result::Result<co::DISP_CHANGE, co::DISP_CHANGE> {
+        let position = devmode.dmPosition();
+        log::trace!(
+            "Applying DEVMODE to `{}`: fields {:?}, {}x{} @ {} Hz, position {},{}, orientation {:?}, fixed output {:?}, flags {:?}",
+            self.name,
+            devmode.dmFields,
+            devmode.dmPelsWidth,
+            devmode.dmPelsHeight,
+            devmode.dmDisplayFrequency,
+            position.x,
+            position.y,
+            devmode.dmDisplayOrientation(),
+            devmode.dmDisplayFixedOutput(),
+            flags
+        );
+
+        change_display_settings(Some(&self.name), Some(devmode), flags)
+    }
+}
+
+/// Fallbacks for other platforms, where display sets can only be created from fake displays or snapshots
+#[cfg(not(windows))]
+impl DisplayProperties {
+    /// Always fails, as settings can only be fetched on Windows
+    pub fn refresh_settings(&self) -> Result {
+        Err(DisplayPropertiesError::Unsupported(
+            "display settings can only be fetched on Windows",
+        ))
+    }
+
+    /// Always fails, as settings can only be applied on Windows
+    pub fn apply(&self) -> Result {
+        Err(DisplayPropertiesError::Unsupported(
+            "display settings can only be applied on Windows",
+        ))
+    }
+
+    /// Always fails, as settings can only be tested on Windows
+    pub fn test_settings(&self, _settings: &DisplaySettings) -> Result<bool> {
+        Err(DisplayPropertiesError::Unsupported(
+            "display settings can only be tested on Windows",
+        ))
+    }
+}
+
+#[cfg(windows)]
+impl TryFrom<&DISPLAY_DEVICE> for DisplayProperties {
+    type Error = DisplayPropertiesError;
+
+    /// See `DisplayProperties::from_winsafe`
+    fn try_from(device: &DISPLAY_DEVICE) -> Result<Self> {
+        Self::from_winsafe(device)
+    }
+}
+
+/// Calls `ChangeDisplaySettingsEx`
+#[cfg(windows)]
+pub(crate) fn change_display_settings(
+    name: Option<&str>,
+    devmode: Option<&mut winsafe::DEVMODE>,
+    flags: co::CDS,
+) -> std::result::Result<co::DISP_CHANGE, co::DISP_CHANGE> {
+    winsafe::ChangeDisplaySettingsEx(name, devmode, flags)
 }
 
 /// Provides methods to set properties of `winsafe::DEVMODE`
+#[cfg(windows)]
 trait FromDisplaySettings {
     fn set_position(&mut self, position: Position);
     fn set_orientation(&mut self, orientation: Orientation);
     fn set_fixed_output(&mut self, fixed_output: FixedOutput);
     fn set_resolution(&mut self, resolution: Resolution);
+    fn set_frequency(&mut self, frequency: u32);
 
     /// Converts display settings into a `winsafe::DEVMODE` struct
     fn from_display_settings(
@@ -146,9 +605,13 @@ trait FromDisplaySettings {
     }
 }
 
+#[cfg(windows)]
 impl FromDisplaySettings for winsafe::DEVMODE {
     fn set_position(&mut self, position: Position) {
-        self.set_dmPosition(position.0);
+        self.set_dmPosition(POINT {
+            x: position.x,
+            y: position.y,
+        });
         self.dmFields |= winsafe::co::DM::POSITION;
     }
 
@@ -167,63 +630,107 @@ impl FromDisplaySettings for winsafe::DEVMODE {
         self.dmPelsHeight = resolution.height;
         self.dmFields |= winsafe::co::DM::PELSWIDTH | winsafe::co::DM::PELSHEIGHT;
     }
+
+    fn set_frequency(&mut self, frequency: u32) {
+        self.dmDisplayFrequency = frequency;
+        self.dmFields |= winsafe::co::DM::DISPLAYFREQUENCY;
+    }
 }
 
 /// Contains the position of a display
 #[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
-pub struct Position(POINT);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    x: i32,
+    y: i32,
+}
 
 impl Position {
     /// Create a position
     pub fn new(x: i32, y: i32) -> Self {
-        Self(POINT { x, y })
+        Self { x, y }
+    }
+
+    /// Returns the horizontal coordinate
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    /// Returns the vertical coordinate
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+
+    /// Adds the `other` position, returning `None` if a coordinate overflows
+    pub fn checked_add(&self, other: Position) -> Option<Position> {
+        Some(Position::new(
+            self.x.checked_add(other.x)?,
+            self.y.checked_add(other.y)?,
+        ))
+    }
+
+    /// Subtracts the `other` position, returning `None` if a coordinate overflows
+    pub fn checked_sub(&self, other: Position) -> Option<Position> {
+        Some(Position::new(
+            self.x.checked_sub(other.x)?,
+            self.y.checked_sub(other.y)?,
+        ))
+    }
+
+    /// Returns the Manhattan distance (`|dx| + |dy|`) to the `other` position
+    pub fn manhattan_distance(&self, other: &Position) -> i64 {
+        (i64::from(self.x) - i64::from(other.x)).abs()
+            + (i64::from(self.y) - i64::from(other.y)).abs()
     }
 }
 
 impl Add for Position {
     type Output = Self;
 
+    /// Saturates at the bounds of `i32` instead of overflowing, see `Position::checked_add`
     fn add(self, other: Self) -> Self {
-        Self(POINT {
-            x: self.0.x + other.0.x,
-            y: self.0.y + other.0.y,
-        })
+        Self {
+            x: self.x.saturating_add(other.x),
+            y: self.y.saturating_add(other.y),
+        }
     }
 }
 
 impl Sub for Position {
     type Output = Self;
 
+    /// Saturates at the bounds of `i32` instead of overflowing, see `Position::checked_sub`
     fn sub(self, other: Self) -> Self {
-        Self(POINT {
-            x: self.0.x - other.0.x,
-            y: self.0.y - other.0.y,
-        })
+        Self {
+            x: self.x.saturating_sub(other.x),
+            y: self.y.saturating_sub(other.y),
+        }
     }
 }
 
 impl Neg for Position {
     type Output = Self;
 
+    /// Saturates at `i32::MAX` for coordinates of `i32::MIN`
     fn neg(self) -> Self::Output {
-        Self(POINT {
-            x: -self.0.x,
-            y: -self.0.y,
-        })
+        Self {
+            x: self.x.saturating_neg(),
+            y: self.y.saturating_neg(),
+        }
     }
 }
 
 impl fmt::Display for Position {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {})", self.0.x, self.0.y)
+        write!(f, "({}, {})", self.x, self.y)
     }
 }
 
 impl fmt::Debug for Position {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("Point")
-            .field(&self.0.x)
-            .field(&self.0.y)
+            .field(&self.x)
+            .field(&self.y)
             .finish()
     }
 }
@@ -255,16 +762,40 @@ impl FromStr for Position {
 
 /// Contains the resolution of a display
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Resolution {
     pub width: u32,
     pub height: u32,
 }
 
 impl Resolution {
+    /// Common standard resolutions, ordered by their number of pixels
+    /// Useful as a fallback list for pickers when the modes of a display are unknown, e.g. with mock displays.
+    pub const COMMON: &'static [Resolution] = &[
+        Resolution::new(1280, 720),
+        Resolution::new(1366, 768),
+        Resolution::new(1600, 900),
+        Resolution::new(1920, 1080),
+        Resolution::new(1920, 1200),
+        Resolution::new(2560, 1080),
+        Resolution::new(2560, 1440),
+        Resolution::new(3440, 1440),
+        Resolution::new(3840, 1600),
+        Resolution::new(5120, 1440),
+        Resolution::new(3840, 2160),
+        Resolution::new(5120, 2880),
+        Resolution::new(7680, 4320),
+    ];
+
     /// Creates a new resolution
-    pub fn new(width: u32, height: u32) -> Self {
+    pub const fn new(width: u32, height: u32) -> Self {
         Self { width, height }
     }
+
+    /// Returns the number of pixels, computed in `u64` so it can't overflow
+    pub fn pixel_count(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
 }
 
 impl fmt::Display for Resolution {
@@ -298,8 +829,125 @@ impl FromStr for Resolution {
     }
 }
 
+/// Contains the area a display covers on the desktop
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Rectangle {
+    pub position: Position,
+    pub size: Resolution,
+}
+
+impl Rectangle {
+    /// Creates a rectangle from its top left corner and its size
+    pub fn new(position: Position, size: Resolution) -> Self {
+        Self { position, size }
+    }
+
+    /// The x coordinate of the left edge
+    pub fn left(&self) -> i32 {
+        self.position.x()
+    }
+
+    /// The y coordinate of the top edge
+    pub fn top(&self) -> i32 {
+        self.position.y()
+    }
+
+    /// The x coordinate of the right edge (exclusive), saturating at `i32::MAX`
+    pub fn right(&self) -> i32 {
+        self.left().saturating_add_unsigned(self.size.width)
+    }
+
+    /// The y coordinate of the bottom edge (exclusive), saturating at `i32::MAX`
+    pub fn bottom(&self) -> i32 {
+        self.top().saturating_add_unsigned(self.size.height)
+    }
+
+    /// The center of the rectangle, rounded towards the top left and saturating at `i32::MAX`
+    pub fn center(&self) -> Position {
+        Position::new(
+            self.left().saturating_add_unsigned(self.size.width / 2),
+            self.top().saturating_add_unsigned(self.size.height / 2),
+        )
+    }
+
+    /// Whether both rectangles overlap; rectangles that only share an edge do not intersect
+    pub fn intersects(&self, other: &Rectangle) -> bool {
+        self.left() < other.right()
+            && other.left() < self.right()
+            && self.top() < other.bottom()
+            && other.top() < self.bottom()
+    }
+
+    /// Whether the given `point` lies within the rectangle
+    pub fn contains(&self, point: Position) -> bool {
+        (self.left()..self.right()).contains(&point.x())
+            && (self.top()..self.bottom()).contains(&point.y())
+    }
+
+    /// Whether both rectangles overlap or share (a part of) an edge; touching corners do not count
+    pub fn touches(&self, other: &Rectangle) -> bool {
+        let overlaps_x = self.left() < other.right() && other.left() < self.right();
+        let overlaps_y = self.top() < other.bottom() && other.top() < self.bottom();
+        let adjacent_x = self.right() == other.left() || other.right() == self.left();
+        let adjacent_y = self.bottom() == other.top() || other.bottom() == self.top();
+
+        (overlaps_y && (overlaps_x || adjacent_x)) || (overlaps_x && adjacent_y)
+    }
+
+    /// Returns how far `other` has to move to share an edge with this rectangle
+    /// If the rectangles are diagonal to each other, `other` is moved next to this rectangle with aligned top edges
+    /// Moves beyond the range of `i32` saturate.
+    pub fn gap_to(&self, other: &Rectangle) -> Position {
+        let dx = if other.left() >= self.right() {
+            self.right().saturating_sub(other.left())
+        } else if other.right() <= self.left() {
+            self.left().saturating_sub(other.right())
+        } else {
+            0
+        };
+        let dy = if other.top() >= self.bottom() {
+            self.bottom().saturating_sub(other.top())
+        } else if other.bottom() <= self.top() {
+            self.top().saturating_sub(other.bottom())
+        } else {
+            0
+        };
+
+        match (dx, dy) {
+            (dx, 0) => Position::new(dx, 0),
+            (0, dy) => Position::new(0, dy),
+            (dx, _) => Position::new(dx, self.top().saturating_sub(other.top())),
+        }
+    }
+
+    /// The length of the move returned by `gap_to`
+    pub(crate) fn gap_length(&self, other: &Rectangle) -> i64 {
+        self.gap_to(other).manhattan_distance(&Position::default())
+    }
+
+    /// Returns the smallest rectangle containing both rectangles
+    pub fn union(&self, other: &Rectangle) -> Rectangle {
+        let left = self.left().min(other.left());
+        let top = self.top().min(other.top());
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+
+        Rectangle::new(
+            Position::new(left, top),
+            Resolution::new(right.abs_diff(left), bottom.abs_diff(top)),
+        )
+    }
+}
+
 /// Contains the orientation of a display
+/// Serializes as a lowercase tag (e.g. `landscapeflipped`) that deserializes back via `FromStr`
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Orientation {
     Landscape,        // default
     LandscapeFlipped, // upside-down
@@ -309,6 +957,7 @@ pub enum Orientation {
 
 impl Orientation {
     /// Creates a new orientation from `winsafe::co::DMD0`
+    #[cfg(windows)]
     fn from_winsafe(co_dmdo: co::DMDO) -> Result<Self> {
         match co_dmdo {
             co::DMDO::DEFAULT => Ok(Orientation::Landscape),
@@ -321,7 +970,36 @@ impl Orientation {
         }
     }
 
+    /// Returns whether the display is rotated by 90 or 270 degrees, so it is taller than wide
+    pub fn is_portrait(&self) -> bool {
+        matches!(self, Orientation::Portrait | Orientation::PortraitFlipped)
+    }
+
+    /// Returns the clockwise rotation from `Landscape` in degrees, following `DEVMODE::dmDisplayOrientation`
+    /// Note that `PortraitFlipped` (rotated left) is 90 degrees and `Portrait` (rotated right) is 270 degrees
+    pub fn degrees(&self) -> u32 {
+        match self {
+            Orientation::Landscape => 0,
+            Orientation::PortraitFlipped => 90,
+            Orientation::LandscapeFlipped => 180,
+            Orientation::Portrait => 270,
+        }
+    }
+
+    /// Creates an orientation from a rotation in degrees, see `Orientation::degrees`
+    /// Returns `None` unless `degrees` is one of 0, 90, 180 or 270
+    pub fn from_degrees(degrees: u32) -> Option<Self> {
+        match degrees {
+            0 => Some(Orientation::Landscape),
+            90 => Some(Orientation::PortraitFlipped),
+            180 => Some(Orientation::LandscapeFlipped),
+            270 => Some(Orientation::Portrait),
+            _ => None,
+        }
+    }
+
     /// Creates the winsafe orientation struct
+    #[cfg(windows)]
     fn to_winsafe(self) -> co::DMDO {
         match self {
             Orientation::Landscape => co::DMDO::DEFAULT,
@@ -364,8 +1042,25 @@ impl FromStr for Orientation {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Orientation {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// Contains the fixed output of a display
+/// Serializes as a lowercase tag (e.g. `stretch`) that deserializes back via `FromStr`
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum FixedOutput {
     Default,
     Stretch,
@@ -374,6 +1069,7 @@ pub enum FixedOutput {
 
 impl FixedOutput {
     /// Creates a new fixed output struct from `winsafe::co::DMDF0`
+    #[cfg(windows)]
     fn from_winsafe(co_dmdfo: co::DMDFO) -> Result<Self> {
         match co_dmdfo {
             co::DMDFO::DEFAULT => Ok(FixedOutput::Default),
@@ -386,6 +1082,7 @@ impl FixedOutput {
     }
 
     /// Creates a winsafe struct
+    #[cfg(windows)]
     fn to_winsafe(self) -> co::DMDFO {
         match self {
             FixedOutput::Default => co::DMDFO::DEFAULT,
@@ -424,3 +1121,292 @@ impl FromStr for FixedOutput {
         }
     }
 }
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FixedOutput {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rectangle(x: i32, y: i32, width: u32, height: u32) -> Rectangle {
+        Rectangle::new(Position::new(x, y), Resolution::new(width, height))
+    }
+
+    #[test]
+    fn rectangle_bounds() {
+        let bounds = rectangle(-1920, 100, 1920, 1080);
+
+        assert_eq!(bounds.left(), -1920);
+        assert_eq!(bounds.top(), 100);
+        assert_eq!(bounds.right(), 0);
+        assert_eq!(bounds.bottom(), 1180);
+        assert!(bounds.contains(Position::new(-1920, 100)));
+        assert!(!bounds.contains(Position::new(0, 100)));
+        assert!(!bounds.contains(Position::new(-1, 1180)));
+    }
+
+    #[test]
+    fn rectangle_center_rounds_towards_the_top_left() {
+        assert_eq!(
+            rectangle(0, 0, 1920, 1080).center(),
+            Position::new(960, 540)
+        );
+        assert_eq!(rectangle(-3, -3, 3, 3).center(), Position::new(-2, -2));
+    }
+
+    #[test]
+    fn rectangle_union_contains_both_rectangles() {
+        let primary = rectangle(0, 0, 1920, 1080);
+        let left = rectangle(-1080, -420, 1080, 1920);
+
+        assert_eq!(primary.union(&left), rectangle(-1080, -420, 3000, 1920));
+        assert_eq!(left.union(&primary), primary.union(&left));
+        assert_eq!(primary.union(&primary), primary);
+    }
+
+    #[test]
+    fn rectangles_touch_at_edges_but_not_corners() {
+        let bounds = rectangle(0, 0, 1920, 1080);
+
+        assert!(bounds.touches(&rectangle(1920, 0, 100, 100)));
+        assert!(!bounds.intersects(&rectangle(1920, 0, 100, 100)));
+        assert!(!bounds.touches(&rectangle(1920, 1080, 100, 100)));
+        assert!(bounds.intersects(&rectangle(1900, 1000, 100, 100)));
+    }
+
+    #[test]
+    fn rectangle_gap_to() {
+        let bounds = rectangle(0, 0, 1920, 1080);
+
+        assert_eq!(
+            bounds.gap_to(&rectangle(2000, 10, 100, 100)),
+            Position::new(-80, 0)
+        );
+        assert_eq!(
+            bounds.gap_to(&rectangle(10, -300, 100, 100)),
+            Position::new(0, 200)
+        );
+        assert_eq!(
+            bounds.gap_to(&rectangle(2000, 2000, 100, 100)),
+            Position::new(-80, -2000)
+        );
+    }
+
+    #[test]
+    fn rectangle_edges_saturate_at_i32_max() {
+        let bounds = rectangle(i32::MAX - 10, i32::MAX, 1920, u32::MAX);
+
+        assert_eq!(bounds.right(), i32::MAX);
+        assert_eq!(bounds.bottom(), i32::MAX);
+        assert_eq!(bounds.center(), Position::new(i32::MAX, i32::MAX));
+    }
+
+    #[test]
+    fn rectangle_union_spans_the_whole_i32_range() {
+        let union = rectangle(i32::MIN, i32::MIN, 10, 10).union(&rectangle(
+            i32::MAX - 10,
+            i32::MAX - 10,
+            10,
+            10,
+        ));
+
+        assert_eq!(union.position, Position::new(i32::MIN, i32::MIN));
+        assert_eq!(union.size, Resolution::new(u32::MAX, u32::MAX));
+    }
+
+    #[test]
+    fn rectangle_gap_saturates_at_i32_min() {
+        let left = rectangle(i32::MIN, 0, 10, 10);
+        let right = rectangle(i32::MAX - 10, 20, 10, 10);
+
+        assert_eq!(left.gap_to(&right), Position::new(i32::MIN, -20));
+        assert_eq!(right.gap_to(&left), Position::new(i32::MAX, 20));
+    }
+
+    #[test]
+    fn display_settings_round_trip_through_strings() {
+        let settings = DisplaySettings {
+            position: Position::new(-1920, 120),
+            resolution: Resolution::new(1080, 1920),
+            orientation: Orientation::PortraitFlipped,
+            fixed_output: FixedOutput::Stretch,
+        };
+
+        assert_eq!(settings.to_string(), "1080x1920,-1920,120,Left,Stretch");
+        assert_eq!(
+            settings.to_string().parse::<DisplaySettings>().unwrap(),
+            settings
+        );
+    }
+
+    #[test]
+    fn display_settings_parse_optional_parts() {
+        let settings: DisplaySettings = "2560x1440,landscape,center".parse().unwrap();
+        assert_eq!(settings.position, Position::default());
+        assert_eq!(settings.fixed_output, FixedOutput::Center);
+
+        let settings: DisplaySettings = "2560x1440,10,20".parse().unwrap();
+        assert_eq!(settings.position, Position::new(10, 20));
+        assert_eq!(settings.fixed_output, FixedOutput::Default);
+
+        assert!(matches!(
+            "2560x1440,x,20".parse::<DisplaySettings>(),
+            Err(ParseDisplaySettingsError::Position(_))
+        ));
+        assert!(matches!(
+            "2560x1440,0,0,left,center,1".parse::<DisplaySettings>(),
+            Err(ParseDisplaySettingsError::PartCount(6))
+        ));
+        assert!(matches!(
+            "2560x1440,0,0,left,center,stretch".parse::<DisplaySettings>(),
+            Err(ParseDisplaySettingsError::PartCount(6))
+        ));
+    }
+
+    #[test]
+    fn display_settings_report_wrong_part_counts() {
+        for (spec, count) in [
+            ("1920x1080,0", 2),
+            ("1920x1080,", 2),
+            ("1920x1080,,", 3),
+            ("1920x1080,5,6,7", 4),
+        ] {
+            assert!(
+                matches!(
+                    spec.parse::<DisplaySettings>(),
+                    Err(ParseDisplaySettingsError::PartCount(c)) if c == count
+                ),
+                "{} should have the wrong number of parts",
+                spec
+            );
+        }
+        assert!(matches!(
+            "1920x1080@144,0,0,landscape".parse::<DisplaySettings>(),
+            Err(ParseDisplaySettingsError::Frequency)
+        ));
+    }
+
+    #[test]
+    fn partial_display_settings_parse_only_the_given_parts() {
+        let spec: PartialDisplaySettings = "2560x1440".parse().unwrap();
+        let mut settings = DisplaySettings {
+            position: Position::new(1920, 0),
+            orientation: Orientation::Portrait,
+            ..Default::default()
+        };
+        spec.apply_to(&mut settings);
+
+        assert_eq!(settings.resolution, Resolution::new(2560, 1440));
+        assert_eq!(settings.position, Position::new(1920, 0));
+        assert_eq!(settings.orientation, Orientation::Portrait);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn orientation_and_fixed_output_round_trip_through_json() {
+        assert_eq!(
+            serde_json::to_string(&Orientation::LandscapeFlipped).unwrap(),
+            "\"landscapeflipped\""
+        );
+        assert_eq!(
+            serde_json::to_string(&FixedOutput::Stretch).unwrap(),
+            "\"stretch\""
+        );
+
+        for orientation in [
+            Orientation::Landscape,
+            Orientation::LandscapeFlipped,
+            Orientation::Portrait,
+            Orientation::PortraitFlipped,
+        ] {
+            let json = serde_json::to_string(&orientation).unwrap();
+            assert_eq!(
+                serde_json::from_str::<Orientation>(&json).unwrap(),
+                orientation
+            );
+        }
+        for fixed_output in [
+            FixedOutput::Default,
+            FixedOutput::Stretch,
+            FixedOutput::Center,
+        ] {
+            let json = serde_json::to_string(&fixed_output).unwrap();
+            assert_eq!(
+                serde_json::from_str::<FixedOutput>(&json).unwrap(),
+                fixed_output
+            );
+        }
+    }
+
+    #[test]
+    fn resolution_pixel_count() {
+        assert_eq!(Resolution::new(1920, 1080).pixel_count(), 2_073_600);
+        assert_eq!(Resolution::new(0, 1080).pixel_count(), 0);
+        assert_eq!(
+            Resolution::new(u32::MAX, u32::MAX).pixel_count(),
+            u64::from(u32::MAX) * u64::from(u32::MAX)
+        );
+    }
+
+    #[test]
+    fn common_resolutions_are_sorted_by_pixel_count_without_duplicates() {
+        for pair in Resolution::COMMON.windows(2) {
+            assert!(
+                pair[0].pixel_count() < pair[1].pixel_count(),
+                "{} should come before {}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn position_arithmetic_saturates_at_i32_bounds() {
+        let max = Position::new(i32::MAX, 0);
+        let min = Position::new(i32::MIN, 0);
+        let one = Position::new(1, 0);
+
+        assert_eq!(max + one, max);
+        assert_eq!(min - one, min);
+        assert_eq!(-min, max);
+
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(min.checked_sub(one), None);
+        // negating is subtracting from the origin
+        assert_eq!(Position::default().checked_sub(min), None);
+
+        assert_eq!(max.checked_add(-one), Some(Position::new(i32::MAX - 1, 0)));
+        assert_eq!(min.checked_sub(-one), Some(Position::new(i32::MIN + 1, 0)));
+    }
+
+    #[test]
+    fn device_instance_path_is_taken_from_the_monitor_id() {
+        let mut properties = DisplayProperties {
+            name: r"\\.\DISPLAY1".to_string(),
+            string: "NVIDIA GeForce GTX 1080".to_string(),
+            key: r"\Registry\Machine\System\CurrentControlSet\Control\Video\{23A77BF7-ED96-40EC-AF06-9B1F4867732A}\0000".to_string(),
+            monitor_id: r"\\?\DISPLAY#GSM5B08#5&1f3ab4a&0&UID4352#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}".to_string(),
+            active: true,
+            primary: Cell::new(true),
+            settings: None,
+        };
+        assert_eq!(
+            properties.device_instance_path().as_deref(),
+            Some(r"DISPLAY\GSM5B08\5&1f3ab4a&0&UID4352")
+        );
+
+        properties.monitor_id = String::new();
+        assert_eq!(properties.device_instance_path(), None);
+        properties.monitor_id = r"PCI\VEN_10DE&DEV_1B80&SUBSYS_119E10DE&REV_A1".to_string();
+        assert_eq!(properties.device_instance_path(), None);
+    }
+}
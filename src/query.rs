@@ -0,0 +1,76 @@
+use crate::{Display, DisplaySet};
+
+/// Describes which displays to look for, see `DisplaySet::find` and `DisplaySet::filter`
+/// Criteria that are not set match every display
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisplayQuery {
+    /// The exact name of the display, e.g. `\\.\DISPLAY1`
+    pub name: Option<String>,
+    /// A part of the device key of the display
+    pub key_contains: Option<String>,
+    /// Whether the display is the primary display
+    pub primary: Option<bool>,
+    /// Whether the display is active
+    pub active: Option<bool>,
+}
+
+impl DisplayQuery {
+    /// Creates a query that matches every display
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only matches the display with the given name
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Only matches displays whose device key contains `key`
+    pub fn key_contains(mut self, key: &str) -> Self {
+        self.key_contains = Some(key.to_string());
+        self
+    }
+
+    /// Only matches displays that are (or are not) the primary display
+    pub fn primary(mut self, primary: bool) -> Self {
+        self.primary = Some(primary);
+        self
+    }
+
+    /// Only matches displays that are (or are not) active
+    pub fn active(mut self, active: bool) -> Self {
+        self.active = Some(active);
+        self
+    }
+}
+
+impl Display<'_> {
+    /// Returns whether this display meets all criteria of the `query`
+    pub fn matches(&self, query: &DisplayQuery) -> bool {
+        query.name.as_deref().is_none_or(|name| self.name() == name)
+            && query
+                .key_contains
+                .as_deref()
+                .is_none_or(|key| self.key().contains(key))
+            && query
+                .primary
+                .is_none_or(|primary| self.is_primary() == primary)
+            && query
+                .active
+                .is_none_or(|active| self.settings().is_some() == active)
+    }
+}
+
+impl DisplaySet {
+    /// Returns the first display that matches the `query`
+    pub fn find(&self, query: &DisplayQuery) -> Option<Display<'_>> {
+        self.displays().find(|display| display.matches(query))
+    }
+
+    /// Returns all displays that match the `query`
+    pub fn filter<'a>(&'a self, query: &'a DisplayQuery) -> impl Iterator<Item = Display<'a>> {
+        self.displays()
+            .filter(move |display| display.matches(query))
+    }
+}
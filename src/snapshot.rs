@@ -0,0 +1,79 @@
+use std::cell::{Cell, RefCell};
+
+use crate::properties::{DisplayProperties, DisplayPropertiesError, DisplaySettings};
+
+/// A read-only copy of a display set without interior mutability
+///
+/// Unlike `DisplaySet`, a snapshot is `Send + Sync`, so it can be handed to other threads.
+/// Changes still have to be made through a `DisplaySet`, see `DisplaySet::from_snapshot` for editing a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplaySnapshot {
+    /// The displays at the time the snapshot was taken
+    pub displays: Vec<DisplaySnapshotEntry>,
+    /// The index of the primary display
+    pub primary_index: usize,
+}
+
+impl DisplaySnapshot {
+    /// Returns the primary display of the snapshot
+    pub fn primary(&self) -> Option<&DisplaySnapshotEntry> {
+        self.displays.get(self.primary_index)
+    }
+}
+
+/// A read-only copy of a single display
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplaySnapshotEntry {
+    pub name: String,
+
+    pub string: String,
+    pub key: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub monitor_id: String,
+
+    pub active: bool,
+
+    pub settings: Option<DisplaySettings>,
+}
+
+impl TryFrom<&DisplayProperties> for DisplaySnapshotEntry {
+    type Error = DisplayPropertiesError;
+
+    /// Fails if the settings of the display are currently borrowed mutably
+    fn try_from(properties: &DisplayProperties) -> Result<Self, Self::Error> {
+        let settings = match &properties.settings {
+            Some(settings) => Some(
+                *settings
+                    .try_borrow()
+                    .map_err(|_| DisplayPropertiesError::Busy(properties.name.to_string()))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            name: properties.name.clone(),
+            string: properties.string.clone(),
+            key: properties.key.clone(),
+            monitor_id: properties.monitor_id.clone(),
+            active: properties.active,
+            settings,
+        })
+    }
+}
+
+impl From<DisplaySnapshotEntry> for DisplayProperties {
+    /// Creates the properties of a display that is not flagged as primary
+    fn from(entry: DisplaySnapshotEntry) -> Self {
+        Self {
+            name: entry.name,
+            string: entry.string,
+            key: entry.key,
+            monitor_id: entry.monitor_id,
+            active: entry.active,
+            primary: Cell::new(false),
+            settings: entry.settings.map(RefCell::new),
+        }
+    }
+}
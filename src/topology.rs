@@ -0,0 +1,73 @@
+use windows::Win32::Graphics::Gdi::{
+    SDC_APPLY, SDC_TOPOLOGY_CLONE, SDC_TOPOLOGY_EXTEND, SDC_TOPOLOGY_EXTERNAL,
+    SDC_TOPOLOGY_INTERNAL,
+};
+
+use crate::{DisplayError, DisplaySet};
+
+type Result<T = ()> = std::result::Result<T, DisplayError>;
+
+/// The display topologies offered by the `Win+P` menu
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Topology {
+    /// Only the internal display, e.g. the screen of a laptop
+    Internal,
+    /// All displays show the same content
+    Clone,
+    /// The desktop is extended across all displays
+    Extend,
+    /// Only the external displays
+    External,
+}
+
+impl Topology {
+    /// Returns the `SDC_TOPOLOGY_*` flag of this topology
+    fn flag(self) -> u32 {
+        match self {
+            Topology::Internal => SDC_TOPOLOGY_INTERNAL,
+            Topology::Clone => SDC_TOPOLOGY_CLONE,
+            Topology::Extend => SDC_TOPOLOGY_EXTEND,
+            Topology::External => SDC_TOPOLOGY_EXTERNAL,
+        }
+    }
+}
+
+impl DisplaySet {
+    /// Switches to the given `topology` like the `Win+P` menu does and queries the displays again
+    /// Windows picks the last layout it used for the topology. Pending changes of this set are discarded.
+    pub fn set_topology(&mut self, topology: Topology) -> Result {
+        self.ensure_live()?;
+        if self.is_mock() {
+            return Ok(());
+        }
+
+        match set_display_config(SDC_APPLY | topology.flag()) {
+            0 => self.reload(),
+            code => Err(DisplayError::Topology(code)),
+        }
+    }
+
+    /// Reverts all displays to the configuration saved in the display database and queries them again
+    /// Discards pending changes as well as changes that were only applied dynamically (see `ApplyOptions`), like an "undo" button.
+    pub fn restore_saved_config(&mut self) -> Result {
+        self.ensure_live()?;
+        if self.is_mock() {
+            return self.reload();
+        }
+
+        match set_display_config(SDC_APPLY | SDC_USE_DATABASE_CURRENT) {
+            0 => self.reload(),
+            code => Err(DisplayError::Topology(code)),
+        }
+    }
+}
+
+/// `SDC_USE_DATABASE_CURRENT`, which the `windows` crate does not define; it combines all topology flags
+const SDC_USE_DATABASE_CURRENT: u32 =
+    SDC_TOPOLOGY_INTERNAL | SDC_TOPOLOGY_CLONE | SDC_TOPOLOGY_EXTEND | SDC_TOPOLOGY_EXTERNAL;
+
+/// Calls `SetDisplayConfig` without paths and modes, which is how topologies are applied
+fn set_display_config(flags: u32) -> i32 {
+    unsafe { windows::Win32::Devices::Display::SetDisplayConfig(&[], &[], flags) }
+}